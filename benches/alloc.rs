@@ -0,0 +1,56 @@
+//! Bytes allocated and time taken per error created in a loop, with
+//! backtraces turned off. Run with `cargo bench --bench alloc`.
+
+use anyhow::backtrace::{self, Policy};
+use anyhow::{anyhow, Context as _, Error};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const ITERATIONS: usize = 1_000_000;
+
+fn bench(name: &str, f: impl Fn(usize) -> Error) {
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        drop(black_box(f(black_box(i))));
+    }
+    let nanos = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    let bytes = (ALLOCATED.load(Ordering::Relaxed) - allocated) / ITERATIONS;
+    println!("{:<20} {:>4} bytes {:>8.1} ns", name, bytes, nanos);
+}
+
+fn other() -> io::Error {
+    io::Error::from(io::ErrorKind::Other)
+}
+
+fn main() {
+    backtrace::set_policy(Policy::Never);
+
+    bench("anyhow!(literal)", |_| anyhow!("oh no!"));
+    bench("anyhow!(format)", |i| anyhow!("oh no! {}", i));
+    bench("Error::new", |_| Error::new(other()));
+    bench("context", |_| {
+        Err::<(), _>(other()).context("oh no!").unwrap_err()
+    });
+}
@@ -67,15 +67,36 @@ fn main() {
         println!("cargo:rustc-check-cfg=cfg(anyhow_nightly_testing)");
         println!("cargo:rustc-check-cfg=cfg(anyhow_no_clippy_format_args)");
         println!("cargo:rustc-check-cfg=cfg(anyhow_no_core_error)");
+        println!("cargo:rustc-check-cfg=cfg(anyhow_no_diagnostic_do_not_recommend)");
+        println!("cargo:rustc-check-cfg=cfg(anyhow_no_diagnostic_namespace)");
+        println!("cargo:rustc-check-cfg=cfg(anyhow_no_is_terminal)");
         println!("cargo:rustc-check-cfg=cfg(error_generic_member_access)");
     }
 
+    if rustc < 70 {
+        // std::io::IsTerminal
+        // https://blog.rust-lang.org/2023/06/01/Rust-1.70.0.html#isterminal
+        println!("cargo:rustc-cfg=anyhow_no_is_terminal");
+    }
+
+    if rustc < 78 {
+        // #[diagnostic::on_unimplemented]
+        // https://blog.rust-lang.org/2024/05/02/Rust-1.78.0.html#diagnostic-attributes
+        println!("cargo:rustc-cfg=anyhow_no_diagnostic_namespace");
+    }
+
     if rustc < 81 {
         // core::error::Error
         // https://blog.rust-lang.org/2024/09/05/Rust-1.81.0.html#coreerrorerror
         println!("cargo:rustc-cfg=anyhow_no_core_error");
     }
 
+    if rustc < 85 {
+        // #[diagnostic::do_not_recommend]
+        // https://blog.rust-lang.org/2025/02/20/Rust-1.85.0.html#diagnosticdo_not_recommend
+        println!("cargo:rustc-cfg=anyhow_no_diagnostic_do_not_recommend");
+    }
+
     if rustc < 85 {
         // #[clippy::format_args]
         // https://doc.rust-lang.org/1.85.1/clippy/attribs.html#clippyformat_args
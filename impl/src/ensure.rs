@@ -0,0 +1,508 @@
+use proc_macro2::{Delimiter, Group, Ident, Literal, Spacing, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream, Parser, Result};
+use syn::{BinOp, Expr, ExprBinary, LitStr, Token};
+
+pub struct Input {
+    // The `$crate` of the anyhow macro that called us.
+    krate: TokenTree,
+    // What to return the error in: `Err`, or `err_into` for `ensure_into!`.
+    ret: Ident,
+    // The caller's tokens exactly as written. Tokens that have been taken apart
+    // by the parser no longer carry the information that rustc uses to
+    // stringify them with their original spacing, but an untouched stream
+    // does.
+    original: TokenStream,
+    cond: Expr,
+    // Everything after the condition's comma, passed through to `anyhow!`.
+    args: Option<TokenStream>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let krate: TokenTree = input.parse()?;
+        let ret: Ident = input.parse()?;
+        let group: Group = input.parse()?;
+        let original = group.stream();
+        let (cond, args) = parse_args.parse2(original.clone())?;
+        Ok(Input {
+            krate,
+            ret,
+            original,
+            cond,
+            args,
+        })
+    }
+}
+
+fn parse_args(input: ParseStream) -> Result<(Expr, Option<TokenStream>)> {
+    if input.is_empty() {
+        return Err(input.error("expected a condition"));
+    }
+    let cond: Expr = input.parse()?;
+    let mut args = None;
+    if !input.is_empty() {
+        input.parse::<Token![,]>()?;
+        if !input.is_empty() {
+            args = Some(input.parse()?);
+        }
+    }
+    Ok((cond, args))
+}
+
+pub fn expand(input: Input) -> TokenStream {
+    let Input {
+        krate,
+        ret,
+        original,
+        cond,
+        args,
+    } = input;
+
+    if let Some(args) = args {
+        return match &cond {
+            Expr::Binary(binary) if binary.attrs.is_empty() && is_comparison(&binary.op) => {
+                expand_with_message(&krate, &ret, &original, binary, &args)
+            }
+            _ => quote! {
+                if #krate::__private::not(#cond) {
+                    return #krate::__private::#ret(#krate::__anyhow!(#args));
+                }
+            },
+        };
+    }
+
+    // Without a trailing comma, the original tokens are exactly the condition.
+    let printed = match original.clone().into_iter().last() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => quote!(#cond),
+        _ => original.clone(),
+    };
+    let msg = if glues_generics(&original) {
+        // stringify! would print the `>` closing the generic arguments glued
+        // to the comparison operator after it.
+        let msg = format!("Condition failed: `{}`", print(cond.to_token_stream()));
+        Literal::string(&msg).into_token_stream()
+    } else {
+        quote! {
+            #krate::__private::concat!("Condition failed: `", #krate::__private::stringify!(#printed), "`")
+        }
+    };
+
+    match &cond {
+        Expr::Binary(binary) if binary.attrs.is_empty() && is_comparison(&binary.op) => {
+            let lhs = &binary.left;
+            let op = &binary.op;
+            let rhs = &binary.right;
+
+            // Not nameable from the caller's expressions.
+            let lhs_var = Ident::new("lhs", Span::mixed_site());
+            let rhs_var = Ident::new("rhs", Span::mixed_site());
+
+            quote! {
+                match (&(#lhs), &(#rhs)) {
+                    (#lhs_var, #rhs_var) => {
+                        if !(#lhs_var #op #rhs_var) {
+                            #[allow(unused_imports)]
+                            use #krate::__private::{BothDebug, NotBothDebug};
+                            return #krate::__private::#ret((#lhs_var, #rhs_var).__dispatch_ensure(#msg));
+                        }
+                    }
+                }
+            }
+        }
+        Expr::Binary(binary) if binary.attrs.is_empty() && is_logical(&binary.op) => {
+            let mut terms = Vec::new();
+            flatten(&cond, &binary.op, &mut terms);
+            if terms.iter().any(|term| is_logical_expr(term)) {
+                // Mixed && and ||, same as the macro_rules implementation.
+                return quote! {
+                    if #krate::__private::not(#cond) {
+                        return #krate::__private::#ret(#krate::Error::msg(#msg));
+                    }
+                };
+            }
+            let terms = terms.into_iter().map(|term| match term {
+                Expr::Binary(binary) if binary.attrs.is_empty() && is_comparison(&binary.op) => {
+                    let lhs = &binary.left;
+                    let op = &binary.op;
+                    let rhs = &binary.right;
+                    let lhs_name = Literal::string(&print(lhs.to_token_stream()));
+                    let rhs_name = Literal::string(&print(rhs.to_token_stream()));
+                    quote!([cmp (#lhs) (#lhs_name) #op (#rhs) (#rhs_name)])
+                }
+                _ => {
+                    let name = Literal::string(&print(term.to_token_stream()));
+                    quote!([atom (#term) (#name)])
+                }
+            });
+            let op = &binary.op;
+            quote! {
+                #krate::__power_ensure!(@logic (#ret #original) (#op) [#(#terms)*])
+            }
+        }
+        _ => quote! {
+            if #krate::__private::not(#cond) {
+                return #krate::__private::#ret(#krate::Error::msg(#msg));
+            }
+        },
+    }
+}
+
+// A comparison with a message of its own, which keeps the operands around for
+// anyhow::ensure::set_custom_message.
+fn expand_with_message(
+    krate: &TokenTree,
+    ret: &Ident,
+    original: &TokenStream,
+    binary: &ExprBinary,
+    args: &TokenStream,
+) -> TokenStream {
+    let lhs = &binary.left;
+    let op = &binary.op;
+    let rhs = &binary.right;
+
+    // The condition is everything before the comma preceding the message.
+    let cond_len = original.clone().into_iter().count() - args.clone().into_iter().count() - 1;
+    let cond: TokenStream = original.clone().into_iter().take(cond_len).collect();
+    let msg = if glues_generics(&cond) {
+        let msg = format!("Condition failed: `{}`", print(binary.to_token_stream()));
+        Literal::string(&msg).into_token_stream()
+    } else {
+        // Taking the original tokens apart would lose their spacing, so the
+        // condition is separated from the message by a macro_rules matcher
+        // and printed with stringify!, the same as without a message.
+        quote! {
+            #krate::__fancy_ensure!(@cond_msg #original)
+        }
+    };
+
+    let lhs_var = Ident::new("lhs", Span::mixed_site());
+    let rhs_var = Ident::new("rhs", Span::mixed_site());
+    let operands_var = Ident::new("operands", Span::mixed_site());
+
+    let is_format_string = {
+        let mut tokens = args.clone().into_iter();
+        let first = tokens.next();
+        let second = tokens.next();
+        let is_str = match &first {
+            Some(TokenTree::Literal(literal)) => {
+                syn::parse2::<LitStr>(literal.to_token_stream()).is_ok()
+            }
+            _ => false,
+        };
+        let then_comma = match &second {
+            None => true,
+            Some(TokenTree::Punct(punct)) => punct.as_char() == ',',
+            Some(_) => false,
+        };
+        is_str && then_comma
+    };
+    let render = if is_format_string {
+        quote! {
+            text(#krate::__private::format_args!(#args), #msg)
+        }
+    } else {
+        quote! {
+            error(#krate::__anyhow!(#args), #msg)
+        }
+    };
+
+    // The operands are no longer borrowed when the message is evaluated.
+    quote! {{
+        let #operands_var = match (&(#lhs), &(#rhs)) {
+            (#lhs_var, #rhs_var) => {
+                if !(#lhs_var #op #rhs_var) {
+                    #[allow(unused_imports)]
+                    use #krate::__private::{BothDebug, NotBothDebug};
+                    #krate::__private::Some((#lhs_var, #rhs_var).__ensure_operands())
+                } else {
+                    #krate::__private::None
+                }
+            }
+        };
+        if let #krate::__private::Some(#operands_var) = #operands_var {
+            return #krate::__private::#ret(#operands_var.#render);
+        }
+    }}
+}
+
+// Whether the `>` closing generic arguments is joined to a comparison
+// operator after it, as in `E::U::<u8>>E::U`.
+fn glues_generics(tokens: &TokenStream) -> bool {
+    let mut depth = 0;
+    // The previous token is `::`, or a `-` joined to this one.
+    let mut after_colons = false;
+    let mut after_minus = false;
+    let mut tokens = tokens.clone().into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let punct = match token {
+            TokenTree::Punct(punct) => punct,
+            TokenTree::Group(group) if glues_generics(&group.stream()) => return true,
+            _ => {
+                after_colons = false;
+                after_minus = false;
+                continue;
+            }
+        };
+        let joint = punct.spacing() == Spacing::Joint;
+        let next = match tokens.peek() {
+            Some(TokenTree::Punct(next)) if joint => Some(next.as_char()),
+            _ => None,
+        };
+        match punct.as_char() {
+            '<' if depth > 0 || after_colons => depth += 1,
+            '>' if depth > 0 && !after_minus => {
+                depth -= 1;
+                if depth == 0 && matches!(next, Some('>' | '=')) {
+                    return true;
+                }
+            }
+            ':' if next == Some(':') => {
+                tokens.next();
+                after_colons = true;
+                after_minus = false;
+                continue;
+            }
+            _ => {}
+        }
+        after_colons = false;
+        after_minus = punct.as_char() == '-' && joint;
+    }
+    false
+}
+
+fn is_comparison(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Eq(_) | BinOp::Ne(_) | BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_),
+    )
+}
+
+fn is_logical(op: &BinOp) -> bool {
+    matches!(op, BinOp::And(_) | BinOp::Or(_))
+}
+
+fn is_logical_expr(expr: &Expr) -> bool {
+    matches!(expr, Expr::Binary(binary) if is_logical(&binary.op))
+}
+
+// Operands of a chain of the same logical operator, left to right.
+fn flatten<'a>(expr: &'a Expr, op: &BinOp, terms: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::Binary(binary)
+            if binary.attrs.is_empty()
+                && std::mem::discriminant(&binary.op) == std::mem::discriminant(op) =>
+        {
+            flatten(&binary.left, op, terms);
+            flatten(&binary.right, op, terms);
+        }
+        _ => terms.push(expr),
+    }
+}
+
+// The names of operands are printed here rather than by stringify!, because
+// the tokens of an operand carved out of the condition have lost their
+// spacing and stringify! would print `v [0]` or `a . len ()`. This handles
+// the expressions that commonly appear in a condition; anything unusual is
+// still readable, just spaced differently than written.
+fn print(tokens: TokenStream) -> String {
+    let mut printer = Printer::new();
+    printer.stream(tokens);
+    printer.out
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum State {
+    // Nothing printed yet.
+    Start,
+    // After an operand: identifier, literal, group, `?`.
+    Value,
+    // After something that binds to the next token: `.`, `::`, unary ops.
+    Tight,
+    // After a binary operator or `,` which is followed by a space.
+    Binary,
+}
+
+struct Printer {
+    out: String,
+    state: State,
+    last_op: String,
+    last_keyword: bool,
+    generics: usize,
+    // Between the `|` of a closure's parameters.
+    closure: bool,
+}
+
+impl Printer {
+    fn new() -> Self {
+        Printer {
+            out: String::new(),
+            state: State::Start,
+            last_op: String::new(),
+            last_keyword: false,
+            generics: 0,
+            closure: false,
+        }
+    }
+
+    fn stream(&mut self, tokens: TokenStream) {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                TokenTree::Ident(ident) => self.word(&ident.to_string()),
+                TokenTree::Literal(literal) => self.word(&literal.to_string()),
+                TokenTree::Group(group) => self.group(&group),
+                TokenTree::Punct(punct) => {
+                    let mut op = punct.as_char().to_string();
+                    let mut spacing = punct.spacing();
+                    if punct.as_char() == '\'' {
+                        if let Some(TokenTree::Ident(ident)) = tokens.next() {
+                            op.push_str(&ident.to_string());
+                        }
+                        self.word(&op);
+                        continue;
+                    }
+                    // The angle brackets of generic arguments are printed
+                    // on their own, so that the `>` closing them is not
+                    // glued to a following comparison operator.
+                    let generic = match punct.as_char() {
+                        '<' => self.last_op == "::",
+                        '>' => self.generics > 0,
+                        _ => false,
+                    };
+                    while spacing == Spacing::Joint && !generic {
+                        match tokens.peek() {
+                            Some(TokenTree::Punct(next)) => {
+                                op.push(next.as_char());
+                                spacing = next.spacing();
+                                tokens.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    self.op(op);
+                }
+            }
+        }
+    }
+
+    fn word(&mut self, word: &str) {
+        if let State::Value | State::Binary = self.state {
+            self.out.push(' ');
+        }
+        self.out.push_str(word);
+        self.state = State::Value;
+        self.last_op.clear();
+        self.last_keyword = is_keyword(word);
+    }
+
+    fn group(&mut self, group: &Group) {
+        let space = match group.delimiter() {
+            Delimiter::Parenthesis | Delimiter::Bracket => {
+                self.state == State::Binary || self.state == State::Value && self.last_keyword
+            }
+            Delimiter::Brace => matches!(self.state, State::Value | State::Binary),
+            Delimiter::None => {
+                self.stream(group.stream());
+                return;
+            }
+        };
+        if space {
+            self.out.push(' ');
+        }
+        let mut inner = Printer::new();
+        inner.stream(group.stream());
+        match group.delimiter() {
+            Delimiter::Parenthesis => {
+                self.out.push('(');
+                self.out.push_str(&inner.out);
+                self.out.push(')');
+            }
+            Delimiter::Bracket => {
+                self.out.push('[');
+                self.out.push_str(&inner.out);
+                self.out.push(']');
+            }
+            _ if inner.out.is_empty() => self.out.push_str("{}"),
+            _ => {
+                self.out.push_str("{ ");
+                self.out.push_str(&inner.out);
+                self.out.push_str(" }");
+            }
+        }
+        self.state = State::Value;
+        self.last_op.clear();
+        self.last_keyword = false;
+    }
+
+    fn op(&mut self, op: String) {
+        let (space, state) = match op.as_str() {
+            "." | ".." | "..=" | "::" => (false, State::Tight),
+            "?" => (false, State::Value),
+            "," | ";" => (false, State::Binary),
+            "<" if self.last_op == "::" => {
+                self.generics += 1;
+                (false, State::Tight)
+            }
+            ">" if self.generics > 0 => {
+                self.generics -= 1;
+                (false, State::Value)
+            }
+            "|" if self.closure => {
+                self.closure = false;
+                (false, State::Binary)
+            }
+            "|" | "||" if self.state != State::Value || self.last_keyword => {
+                self.closure = op == "|";
+                let state = if self.closure {
+                    State::Tight
+                } else {
+                    State::Binary
+                };
+                (
+                    self.state != State::Start && self.state != State::Tight,
+                    state,
+                )
+            }
+            "!" if self.state == State::Value && !self.last_keyword => (false, State::Tight),
+            "&" | "&&" | "!" | "-" | "*" if self.state != State::Value || self.last_keyword => (
+                self.state != State::Start && self.state != State::Tight,
+                State::Tight,
+            ),
+            _ => (self.state != State::Start, State::Binary),
+        };
+        if space {
+            self.out.push(' ');
+        }
+        self.out.push_str(&op);
+        self.state = state;
+        self.last_op = op;
+        self.last_keyword = false;
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "as" | "async"
+            | "await"
+            | "box"
+            | "break"
+            | "const"
+            | "continue"
+            | "else"
+            | "for"
+            | "if"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "move"
+            | "mut"
+            | "ref"
+            | "return"
+            | "unsafe"
+            | "while"
+            | "yield",
+    )
+}
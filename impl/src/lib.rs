@@ -0,0 +1,16 @@
+extern crate proc_macro;
+
+mod ensure;
+
+use proc_macro::TokenStream;
+use syn::parse_macro_input;
+
+// Invoked by anyhow's `ensure!` as `ensure!($crate Err { $($tt)* })`, and by
+// `ensure_into!` with `err_into` in place of `Err`, when the
+// "ensure-proc-macro" feature is enabled.
+#[doc(hidden)]
+#[proc_macro]
+pub fn ensure(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ensure::Input);
+    ensure::expand(input).into()
+}
@@ -0,0 +1,133 @@
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+use crate::backtrace::Backtrace;
+use crate::Severity;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+// Data attached to an anyhow::Error that is not part of the error value
+// itself, including its backtrace. Held behind a nullable pointer so that the
+// common case of an error with no attachments and no captured backtrace costs
+// one word in ErrorImpl and no extra allocation.
+pub(crate) struct Attachments {
+    inner: Option<Box<Inner>>,
+}
+
+struct Inner {
+    #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+    backtrace: Option<Backtrace>,
+    sections: Vec<Section>,
+    code: Option<&'static str>,
+    severity: Option<Severity>,
+    exit_code: Option<u8>,
+    trace: Option<Box<dyn Display + Send + Sync>>,
+    #[cfg(feature = "std")]
+    sampled: bool,
+}
+
+pub(crate) struct Section {
+    pub kind: SectionKind,
+    pub message: Box<dyn Display + Send + Sync>,
+}
+
+#[derive(Copy, Clone)]
+pub(crate) enum SectionKind {
+    Note,
+    Help,
+    Suggestion,
+}
+
+impl SectionKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SectionKind::Note => "note",
+            SectionKind::Help => "help",
+            SectionKind::Suggestion => "suggestion",
+        }
+    }
+}
+
+impl Attachments {
+    pub const fn new() -> Self {
+        Attachments { inner: None }
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        self.inner.get_or_insert_with(|| {
+            Box::new(Inner {
+                #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+                backtrace: None,
+                sections: Vec::new(),
+                code: None,
+                severity: None,
+                exit_code: None,
+                trace: None,
+                #[cfg(feature = "std")]
+                sampled: false,
+            })
+        })
+    }
+
+    #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+    pub fn set_backtrace(&mut self, backtrace: Backtrace) {
+        self.inner_mut().backtrace = Some(backtrace);
+    }
+
+    #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.as_ref()?.backtrace.as_ref()
+    }
+
+    pub fn push_section(&mut self, kind: SectionKind, message: Box<dyn Display + Send + Sync>) {
+        self.inner_mut().sections.push(Section { kind, message });
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        match &self.inner {
+            Some(inner) => &inner.sections,
+            None => &[],
+        }
+    }
+
+    pub fn set_code(&mut self, code: &'static str) {
+        self.inner_mut().code = Some(code);
+    }
+
+    pub fn code(&self) -> Option<&'static str> {
+        self.inner.as_ref()?.code
+    }
+
+    pub fn set_severity(&mut self, severity: Severity) {
+        self.inner_mut().severity = Some(severity);
+    }
+
+    pub fn severity(&self) -> Option<Severity> {
+        self.inner.as_ref()?.severity
+    }
+
+    pub fn set_exit_code(&mut self, exit_code: u8) {
+        self.inner_mut().exit_code = Some(exit_code);
+    }
+
+    pub fn exit_code(&self) -> Option<u8> {
+        self.inner.as_ref()?.exit_code
+    }
+
+    pub fn set_trace(&mut self, trace: Box<dyn Display + Send + Sync>) {
+        self.inner_mut().trace = Some(trace);
+    }
+
+    pub fn trace(&self) -> Option<&(dyn Display + Send + Sync + 'static)> {
+        self.inner.as_ref()?.trace.as_deref()
+    }
+
+    #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+    pub fn set_sampled(&mut self) {
+        self.inner_mut().sampled = true;
+    }
+
+    #[cfg(feature = "std")]
+    pub fn sampled(&self) -> bool {
+        self.inner.as_ref().map_or(false, |inner| inner.sampled)
+    }
+}
@@ -1,3 +1,44 @@
+//! Control over when backtraces are captured.
+//!
+//! By default an [`Error`][crate::Error] captures a backtrace when it is
+//! created, subject to the `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE`
+//! environment variables as described in [`std::backtrace`]. Capturing is
+//! expensive, which matters for errors that are created often and handled
+//! without ever being printed, such as a cache miss converted with `?`. A
+//! [`Policy`] makes it possible to skip capturing for those while keeping full
+//! backtraces for unexpected errors.
+//!
+//! ```
+#![cfg_attr(
+    not(feature = "std"),
+    doc = "# fn main() {} const _: &str = stringify! {"
+)]
+//! use anyhow::backtrace::{self, Policy};
+//!
+//! fn main() {
+//!     backtrace::set_policy(Policy::Filter(|type_name| {
+//!         !type_name.starts_with("my_app::cache::")
+//!     }));
+//!     # const IGNORE: &str = stringify! {
+//!     ...
+//!     # };
+//! }
+#![cfg_attr(not(feature = "std"), doc = "# };")]
+//! ```
+//!
+//! Applications with a notion of stack other than the native one, such as an
+//! async task tree or an interpreter, can additionally register a
+//! [capturer][set_capturer] whose trace is stored with every new error and
+//! shown in its `Debug` representation.
+//!
+//! With the "no-backtrace" Cargo feature, no backtraces are captured or stored
+//! at all, even with "std" enabled, and the code for doing so is left out. The
+//! capture policy then has no effect, and
+//! [`Error::backtrace`][crate::Error::backtrace] returns a disabled backtrace
+//! unless the underlying error provides one of its own.
+//!
+//! [`std::backtrace`]: std::backtrace#environment-variables
+
 #[cfg(feature = "std")]
 pub(crate) use std::backtrace::Backtrace;
 
@@ -5,43 +46,702 @@ pub(crate) use std::backtrace::Backtrace;
 pub(crate) enum Backtrace {}
 
 #[cfg(feature = "std")]
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::{self, Vec};
+use core::fmt::Display;
+use core::mem;
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+use core::panic::Location;
+use core::ptr;
+#[cfg(feature = "std")]
+use core::slice;
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicU32, AtomicU8, AtomicUsize};
+#[cfg(feature = "std")]
+use std::cell::Cell;
+#[cfg(feature = "std")]
+use std::thread_local;
+#[cfg(feature = "std")]
+use std::time::Duration;
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// When to capture a backtrace for a newly created error.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug)]
+pub enum Policy {
+    /// Capture according to the `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE`
+    /// environment variables. This is the default.
+    Env,
+    /// Always capture, regardless of environment variables.
+    Always,
+    /// Never capture.
+    Never,
+    /// Call the function with the [type name] of the error being created.
+    /// If it returns true, capture according to environment variables as
+    /// with `Env`; otherwise do not capture.
+    ///
+    /// The type name is that of the value passed to `Error::new`, `anyhow!`,
+    /// `Error::msg`, `?`, or of the context given to `Option::context`.
+    ///
+    /// [type name]: core::any::type_name
+    Filter(fn(&'static str) -> bool),
+    /// Capture according to environment variables as with `Env`, but at most
+    /// `max` backtraces per origin within each `window` of time, counting
+    /// from the start of every consecutive window of that length on the
+    /// system clock. Errors are only counted while the environment variables
+    /// enable backtraces.
+    ///
+    /// The origin of an error is its type together with the place in the
+    /// program where it was created, such as the `anyhow!` or `bail!`
+    /// invocation, the `?` operator, or the call to `context`. Errors beyond
+    /// the limit are created without a backtrace, and their report says that
+    /// the backtrace was not captured because of sampling.
+    Sample {
+        /// Number of backtraces to capture per origin within a window.
+        max: u32,
+        /// Length of the window after which the count starts over.
+        window: Duration,
+    },
+}
+
+// The process-wide policy is kept in atomics rather than behind a lock, as it
+// is read every time an error is created. POLICY holds which variant it is,
+// and is stored last by set_policy so that the fields of that variant are
+// in place by the time it is read.
+#[cfg(feature = "std")]
+static POLICY: AtomicU8 = AtomicU8::new(ENV);
+#[cfg(feature = "std")]
+static FILTER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+#[cfg(feature = "std")]
+static SAMPLE_MAX: AtomicU32 = AtomicU32::new(0);
+#[cfg(feature = "std")]
+static SAMPLE_WINDOW_SECS: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "std")]
+static SAMPLE_WINDOW_NANOS: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(feature = "std")]
+const ENV: u8 = 0;
+#[cfg(feature = "std")]
+const ALWAYS: u8 = 1;
+#[cfg(feature = "std")]
+const NEVER: u8 = 2;
+#[cfg(feature = "std")]
+const FILTER_FN: u8 = 3;
+#[cfg(feature = "std")]
+const SAMPLE: u8 = 4;
+
+// Number of threads with a thread policy set, so that the thread-local is
+// only looked at while some thread has one.
+#[cfg(feature = "std")]
+static THREAD_POLICIES: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "std")]
+thread_local! {
+    static THREAD_POLICY: Cell<Option<Policy>> = const { Cell::new(None) };
+    #[cfg(not(feature = "no-backtrace"))]
+    // Set when the backtrace for the error about to be constructed on this
+    // thread was skipped by Policy::Sample, and taken by Error::construct.
+    static SAMPLED: Cell<bool> = const { Cell::new(false) };
+}
+
+// Whether Policy::Sample has ever skipped a backtrace, so that SAMPLED is
+// only looked at once it may have been set.
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+static ANY_SAMPLED: AtomicBool = AtomicBool::new(false);
+
+/// Set the process-wide backtrace capture policy.
+#[cfg(feature = "std")]
+pub fn set_policy(policy: Policy) {
+    let variant = match policy {
+        Policy::Env => ENV,
+        Policy::Always => ALWAYS,
+        Policy::Never => NEVER,
+        Policy::Filter(filter) => {
+            FILTER.store(filter as *mut (), Ordering::Relaxed);
+            FILTER_FN
+        }
+        Policy::Sample { max, window } => {
+            let secs = usize::try_from(window.as_secs()).unwrap_or(usize::MAX);
+            SAMPLE_MAX.store(max, Ordering::Relaxed);
+            SAMPLE_WINDOW_SECS.store(secs, Ordering::Relaxed);
+            SAMPLE_WINDOW_NANOS.store(window.subsec_nanos(), Ordering::Relaxed);
+            SAMPLE
+        }
+    };
+    POLICY.store(variant, Ordering::Release);
+}
+
+/// Set a backtrace capture policy for the current thread that takes
+/// precedence over the process-wide one, or remove it by passing `None`.
+#[cfg(feature = "std")]
+pub fn set_thread_policy(policy: Option<Policy>) {
+    let previous = THREAD_POLICY.with(|thread_policy| thread_policy.replace(policy));
+    match (previous, policy) {
+        (None, Some(_)) => {
+            THREAD_POLICIES.fetch_add(1, Ordering::Relaxed);
+        }
+        (Some(_), None) => {
+            THREAD_POLICIES.fetch_sub(1, Ordering::Relaxed);
+        }
+        _ => {}
+    }
+}
+
+/// The backtrace capture policy in effect on the current thread.
+#[cfg(feature = "std")]
+pub fn policy() -> Policy {
+    if THREAD_POLICIES.load(Ordering::Relaxed) != 0 {
+        if let Some(policy) = THREAD_POLICY.with(Cell::get) {
+            return policy;
+        }
+    }
+    match POLICY.load(Ordering::Acquire) {
+        ALWAYS => Policy::Always,
+        NEVER => Policy::Never,
+        FILTER_FN => {
+            let filter = FILTER.load(Ordering::Relaxed);
+            // Safety: FILTER is stored before POLICY is set to FILTER_FN, and
+            // the only pointers ever stored are filter fns.
+            Policy::Filter(unsafe { mem::transmute::<*mut (), fn(&'static str) -> bool>(filter) })
+        }
+        SAMPLE => Policy::Sample {
+            max: SAMPLE_MAX.load(Ordering::Relaxed),
+            window: Duration::new(
+                SAMPLE_WINDOW_SECS.load(Ordering::Relaxed) as u64,
+                SAMPLE_WINDOW_NANOS.load(Ordering::Relaxed),
+            ),
+        },
+        _ => Policy::Env,
+    }
+}
+
+/// Iterator of the distinct backtraces in an error's chain of causes.
+///
+/// Returned by [`Error::backtraces`][crate::Error::backtraces]. Each item is
+/// the backtrace together with the position in
+/// [`Error::chain`][crate::Error::chain] of the cause it belongs to, from the
+/// outermost error to the innermost.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct Backtraces<'a> {
+    inner: vec::IntoIter<(usize, &'a Backtrace)>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Backtraces<'a> {
+    pub(crate) fn new(backtraces: Vec<(usize, &'a Backtrace)>) -> Self {
+        Backtraces {
+            inner: backtraces.into_iter(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> Iterator for Backtraces<'a> {
+    type Item = (usize, &'a Backtrace);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(feature = "std")]
+impl DoubleEndedIterator for Backtraces<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ExactSizeIterator for Backtraces<'_> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// The frames of a captured backtrace, parsed from its `Display`
+/// representation.
+///
+/// The standard library does not yet offer a stable way to inspect the
+/// frames of a [`std::backtrace::Backtrace`]. This type provides the function
+/// name and source location of each frame, in order from the innermost frame,
+/// where the backtrace was captured, outward.
+///
+/// ```
+/// use anyhow::anyhow;
+///
+/// let error = anyhow!("oh no!");
+/// for frame in &error.frames() {
+///     if let (Some(file), Some(line)) = (frame.file(), frame.line()) {
+///         println!("{} at {}:{}", frame.function(), file, line);
+///     }
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct Frames {
+    frames: Vec<Frame>,
+}
+
+/// A single frame of a backtrace. See [`Frames`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    function: String,
+    location: Option<(String, u32, u32)>,
+}
+
+#[cfg(feature = "std")]
+impl Frames {
+    /// Parse the frames of a backtrace. A backtrace that was not captured
+    /// has no frames.
+    pub fn new(backtrace: &Backtrace) -> Self {
+        use alloc::string::ToString;
+        use std::backtrace::BacktraceStatus;
+
+        if backtrace.status() != BacktraceStatus::Captured {
+            return Frames::default();
+        }
+        let rendered = backtrace.to_string();
+        let frames = FrameChunks::new(&rendered)
+            .filter(|chunk| !chunk.function.is_empty())
+            .map(|chunk| Frame {
+                function: chunk.function.to_owned(),
+                location: chunk
+                    .location()
+                    .map(|(file, line, column)| (file.to_owned(), line, column)),
+            })
+            .collect();
+        Frames { frames }
+    }
+
+    /// Iterate over the frames, innermost first.
+    pub fn iter(&self) -> slice::Iter<Frame> {
+        self.frames.iter()
+    }
+
+    /// The number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Whether there are no frames, as is the case when no backtrace was
+    /// captured.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> IntoIterator for &'a Frames {
+    type Item = &'a Frame;
+    type IntoIter = slice::Iter<'a, Frame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
+
+#[cfg(feature = "std")]
+impl IntoIterator for Frames {
+    type Item = Frame;
+    type IntoIter = vec::IntoIter<Frame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.into_iter()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Frame {
+    /// The demangled name of the function, such as `my_app::config::load` or
+    /// `<my_app::Config as core::str::FromStr>::from_str`.
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    /// The path of the source file, if debug info is available.
+    pub fn file(&self) -> Option<&str> {
+        self.location.as_ref().map(|(file, _, _)| file.as_str())
+    }
+
+    /// The line number in the source file, if debug info is available.
+    pub fn line(&self) -> Option<u32> {
+        self.location.as_ref().map(|&(_, line, _)| line)
+    }
+
+    /// The column number in the source file, if debug info is available.
+    pub fn column(&self) -> Option<u32> {
+        self.location.as_ref().map(|&(_, _, column)| column)
+    }
+
+    /// Whether the frame belongs to one of the module path prefixes hidden
+    /// from the report, as configured by
+    /// [`report::set_hidden_frames`][crate::report::set_hidden_frames].
+    pub fn is_hidden(&self) -> bool {
+        is_hidden_frame(&self.function, crate::report::hidden_frames())
+    }
+}
+
+type Capturer = fn() -> Option<Box<dyn Display + Send + Sync>>;
+
+// Either null or a Capturer cast to a data pointer.
+static CAPTURER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Register a function that produces a custom trace for every newly created
+/// error, such as an async task trace, an interpreter stack or the output of
+/// an unwinder for an embedded target.
+///
+/// The trace is available from [`Error::trace`][crate::Error::trace] and is
+/// rendered after the backtrace in the error's `Debug` representation. It is
+/// captured independently of the backtrace [`Policy`]; to have the custom
+/// trace instead of a `std::backtrace::Backtrace`, set `Policy::Never`.
+///
+/// Unlike backtraces, custom traces are available without the "std" feature.
+///
+/// ```
+/// use anyhow::backtrace;
+/// use std::fmt::Display;
+///
+/// # fn current_task_trace() -> String { String::new() }
+/// #
+/// fn capture_task_trace() -> Option<Box<dyn Display + Send + Sync>> {
+///     Some(Box::new(current_task_trace()))
+/// }
+///
+/// fn main() {
+///     backtrace::set_capturer(capture_task_trace);
+///     # const IGNORE: &str = stringify! {
+///     ...
+///     # };
+/// }
+/// ```
+pub fn set_capturer(capturer: fn() -> Option<Box<dyn Display + Send + Sync>>) {
+    CAPTURER.store(capturer as *mut (), Ordering::Release);
+}
+
+/// Unregister the function registered by [`set_capturer`].
+pub fn clear_capturer() {
+    CAPTURER.store(ptr::null_mut(), Ordering::Release);
+}
+
+pub(crate) fn capture_trace() -> Option<Box<dyn Display + Send + Sync>> {
+    let capturer = CAPTURER.load(Ordering::Acquire);
+    if capturer.is_null() {
+        return None;
+    }
+    // Safety: the only non-null pointers ever stored are Capturer fns.
+    let capturer = unsafe { mem::transmute::<*mut (), Capturer>(capturer) };
+    capturer()
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+pub(crate) enum Capture {
+    Env,
+    Force,
+    Skip,
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+pub(crate) fn capture(type_name: &'static str, location: &'static Location<'static>) -> Capture {
+    match policy() {
+        Policy::Env => Capture::Env,
+        Policy::Always => Capture::Force,
+        Policy::Never => Capture::Skip,
+        Policy::Filter(filter) => {
+            if filter(type_name) {
+                Capture::Env
+            } else {
+                Capture::Skip
+            }
+        }
+        Policy::Sample { max, window } => {
+            if !env_enabled() {
+                Capture::Skip
+            } else if sample(type_name, location, max, window) {
+                Capture::Env
+            } else {
+                ANY_SAMPLED.store(true, Ordering::Relaxed);
+                SAMPLED.with(|sampled| sampled.set(true));
+                Capture::Skip
+            }
+        }
+    }
+}
+
+// Whether `Backtrace::capture` captures, going by the same environment
+// variables as the standard library, and likewise read only once.
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+fn env_enabled() -> bool {
+    // 0 = not read yet, 1 = disabled, 2 = enabled.
+    static ENABLED: AtomicU8 = AtomicU8::new(0);
+    match ENABLED.load(Ordering::Relaxed) {
+        0 => {}
+        enabled => return enabled == 2,
+    }
+    let enabled = match std::env::var_os("RUST_LIB_BACKTRACE") {
+        Some(value) => value != "0",
+        None => std::env::var_os("RUST_BACKTRACE").map_or(false, |value| value != "0"),
+    };
+    ENABLED.store(enabled as u8 + 1, Ordering::Relaxed);
+    enabled
+}
+
+// Errors counted by Policy::Sample. Each origin hashes to one of the slots,
+// which holds a key for the origin and the current window together with the
+// count of errors seen for that key. An error with a different key takes the
+// slot over and starts counting from zero, so origins that collide end up
+// with more backtraces rather than fewer.
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+struct Slot {
+    key: AtomicUsize,
+    count: AtomicU32,
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+const SLOTS_LOG2: u32 = 7;
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: Slot = Slot {
+    key: AtomicUsize::new(0),
+    count: AtomicU32::new(0),
+};
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+static SLOTS: [Slot; 1 << SLOTS_LOG2] = [EMPTY_SLOT; 1 << SLOTS_LOG2];
+
+// Counts an error from the given origin, returning whether it is within the
+// limit for the current window.
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+fn sample(
+    type_name: &'static str,
+    location: &'static Location<'static>,
+    max: u32,
+    window: Duration,
+) -> bool {
+    fn mix(a: usize, b: usize) -> usize {
+        (a.rotate_left(5) ^ b).wrapping_mul(0x9e37_79b9_7f4a_7c15_u64 as usize)
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let current = (now.as_nanos() / window.as_nanos().max(1)) as usize;
+    let origin = mix(type_name.as_ptr() as usize, location as *const _ as usize);
+    let slot = &SLOTS[origin >> (usize::BITS - SLOTS_LOG2)];
+    let key = mix(origin, current);
+
+    if slot.key.load(Ordering::Relaxed) != key && slot.key.swap(key, Ordering::Relaxed) != key {
+        slot.count.store(0, Ordering::Relaxed);
+    }
+    // Checked before incrementing so that the count cannot wrap around.
+    slot.count.load(Ordering::Relaxed) < max && slot.count.fetch_add(1, Ordering::Relaxed) < max
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+pub(crate) fn take_sampled() -> bool {
+    ANY_SAMPLED.load(Ordering::Relaxed) && SAMPLED.with(|sampled| sampled.replace(false))
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+pub(crate) fn type_name_of_val<T: ?Sized>(_: &T) -> &'static str {
+    core::any::type_name::<T>()
+}
+
+// Expands to the capture itself rather than calling a helper function, so
+// that the backtrace does not start with a frame inside of anyhow.
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
 macro_rules! backtrace {
-    () => {
-        Some(std::backtrace::Backtrace::capture())
+    (@name $type_name:expr) => {
+        match $crate::backtrace::capture($type_name, core::panic::Location::caller()) {
+            $crate::backtrace::Capture::Env => Some(std::backtrace::Backtrace::capture()),
+            $crate::backtrace::Capture::Force => Some(std::backtrace::Backtrace::force_capture()),
+            $crate::backtrace::Capture::Skip => Some(std::backtrace::Backtrace::disabled()),
+        }
+    };
+    ($ty:ty) => {
+        backtrace!(@name core::any::type_name::<$ty>())
     };
 }
 
-#[cfg(not(feature = "std"))]
+#[cfg(any(not(feature = "std"), feature = "no-backtrace"))]
 macro_rules! backtrace {
-    () => {
+    ($ty:ty) => {
         None
     };
 }
 
-#[cfg(error_generic_member_access)]
+#[cfg(all(error_generic_member_access, not(feature = "no-backtrace")))]
 macro_rules! backtrace_if_absent {
     ($err:expr) => {
         match $crate::nightly::request_ref_backtrace($err as &dyn core::error::Error) {
             Some(_) => None,
-            None => backtrace!(),
+            None => backtrace!(@name $crate::backtrace::type_name_of_val($err)),
         }
     };
 }
 
-#[cfg(all(not(error_generic_member_access), feature = "std"))]
+#[cfg(all(
+    not(error_generic_member_access),
+    feature = "std",
+    not(feature = "no-backtrace"),
+))]
 macro_rules! backtrace_if_absent {
     ($err:expr) => {
-        backtrace!()
+        backtrace!(@name $crate::backtrace::type_name_of_val($err))
     };
 }
 
-#[cfg(all(not(anyhow_no_core_error), not(feature = "std")))]
+#[cfg(any(
+    all(not(anyhow_no_core_error), not(feature = "std")),
+    feature = "no-backtrace",
+))]
 macro_rules! backtrace_if_absent {
     ($err:expr) => {
         None
     };
 }
 
+// Splits the Display representation of a std Backtrace into the lines
+// belonging to each frame: a numbered line with the function name, followed by
+// any number of "at path:line:column" lines.
+#[cfg(feature = "std")]
+pub(crate) struct FrameChunks<'a> {
+    rest: &'a str,
+}
+
+#[cfg(feature = "std")]
+pub(crate) struct FrameChunk<'a> {
+    pub function: &'a str,
+    pub text: &'a str,
+}
+
+#[cfg(feature = "std")]
+impl<'a> FrameChunk<'a> {
+    // The file, line and column from the "at" line, if there is one.
+    pub fn location(&self) -> Option<(&'a str, u32, u32)> {
+        let line = self
+            .text
+            .lines()
+            .skip(1)
+            .find_map(|line| line.trim().strip_prefix("at "))?;
+        // Split from the end, as the path itself may contain colons.
+        let mut parts = line.rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line_number = parts.next()?.parse().ok()?;
+        let file = parts.next()?;
+        Some((file, line_number, column))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> FrameChunks<'a> {
+    pub fn new(backtrace: &'a str) -> Self {
+        FrameChunks { rest: backtrace }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> Iterator for FrameChunks<'a> {
+    type Item = FrameChunk<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let rest = self.rest;
+        let mut end = rest.find('\n').unwrap_or(rest.len());
+        let function = frame_header(&rest[..end]).unwrap_or("");
+        while end < rest.len() {
+            let line_start = end + 1;
+            let line_end = rest[line_start..]
+                .find('\n')
+                .map_or(rest.len(), |i| line_start + i);
+            if frame_header(&rest[line_start..line_end]).is_some() {
+                break;
+            }
+            end = line_end;
+        }
+
+        self.rest = rest.get(end + 1..).unwrap_or("");
+        Some(FrameChunk {
+            function,
+            text: &rest[..end],
+        })
+    }
+}
+
+// Parses "  12: function::name" into "function::name".
+#[cfg(feature = "std")]
+fn frame_header(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return None;
+    }
+    let function = line[digits..].strip_prefix(':')?;
+    Some(function.trim())
+}
+
+// Whether a frame with the given function name is hidden by the given list of
+// module path prefixes. Frames without any module path, like the C runtime's
+// `main` or `<unknown>`, are hidden unless the list is empty.
+#[cfg(feature = "std")]
+pub(crate) fn is_hidden_frame(function: &str, prefixes: &[&str]) -> bool {
+    if prefixes.is_empty() {
+        return false;
+    }
+    let path = frame_path(function);
+    !path.contains("::") || prefixes.iter().any(|prefix| path.starts_with(prefix))
+}
+
+// The module path that a frame belongs to. For a trait impl method like
+// `<&mut my_app::Type as core::fmt::Display>::fmt` this is the path of the
+// Self type, or of the trait if Self is a type without a path such as `fn()`
+// or a closure, whose `<{closure#0} as FnOnce<()>>::call_once` shims contain
+// no code of the closure's own module.
+#[cfg(feature = "std")]
+fn frame_path(function: &str) -> &str {
+    let mut path = function.trim_start_matches('<');
+    loop {
+        let rest = path
+            .trim_start_matches(['&', '*'])
+            .trim_start_matches("const ")
+            .trim_start_matches("mut ")
+            .trim_start_matches("dyn ");
+        if rest.len() == path.len() {
+            break;
+        }
+        path = rest;
+    }
+    if let Some(i) = path.find(" as ") {
+        let self_ty = &path[..i];
+        if self_ty.starts_with("fn(")
+            || self_ty.starts_with('(')
+            || self_ty.starts_with('[')
+            || self_ty.ends_with("{{closure}}")
+            || self_ty.ends_with('}') && self_ty.contains("{closure#")
+        {
+            return &path[i + 4..];
+        }
+    }
+    path
+}
+
 fn _assert_send_sync() {
     fn assert<T: Send + Sync>() {}
     assert::<Backtrace>();
@@ -0,0 +1,79 @@
+/// Static table of long-form explanations for error codes.
+///
+/// A binary can keep a catalog of the codes it attaches to errors with
+/// [`Error::with_code`] and print the explanation of a code on demand, for
+/// example from an `--explain` command line flag.
+///
+/// [`Error::with_code`]: crate::Error::with_code
+///
+/// # Example
+///
+/// ```
+/// use anyhow::{bail, Catalog, Result};
+///
+/// static ERROR_CODES: Catalog = Catalog::new(&[
+///     (
+///         "E0142",
+///         "The lockfile does not match the manifest.\n\
+///          \n\
+///          Run `tool lock` to regenerate it.",
+///     ),
+///     ("E0143", "..."),
+/// ]);
+///
+/// fn check_lockfile() -> Result<()> {
+///     # const IGNORE: &str = stringify! {
+///     ...
+///     # };
+///     bail!(code = "E0142", "lockfile is out of date");
+/// }
+///
+/// fn main() {
+///     # let args = ["tool", "--explain", "E0142"];
+///     # const IGNORE: &str = stringify! {
+///     let args: Vec<String> = std::env::args().collect();
+///     # };
+///     if let ["tool", "--explain", code] = args[..] {
+///         match ERROR_CODES.explain(code) {
+///             Some(explanation) => println!("{}", explanation),
+///             None => eprintln!("unknown error code {}", code),
+///         }
+///         return;
+///     }
+///
+///     if let Err(err) = check_lockfile() {
+///         eprintln!("Error: {:?}", err);
+///         if let Some(code) = err.code().filter(|code| ERROR_CODES.contains(code)) {
+///             eprintln!("For more information, try `tool --explain {}`.", code);
+///         }
+///     }
+/// }
+/// ```
+pub struct Catalog {
+    entries: &'static [(&'static str, &'static str)],
+}
+
+impl Catalog {
+    /// Create a catalog from a list of `(code, explanation)` pairs.
+    pub const fn new(entries: &'static [(&'static str, &'static str)]) -> Self {
+        Catalog { entries }
+    }
+
+    /// Look up the long-form explanation of an error code.
+    pub fn explain(&self, code: &str) -> Option<&'static str> {
+        self.entries
+            .iter()
+            .find(|(entry, _explanation)| *entry == code)
+            .map(|(_code, explanation)| *explanation)
+    }
+
+    /// Returns true if the catalog has an explanation for the given code.
+    pub fn contains(&self, code: &str) -> bool {
+        self.explain(code).is_some()
+    }
+
+    /// Iterate over the `(code, explanation)` pairs in the catalog.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.entries.iter().copied()
+    }
+}
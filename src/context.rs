@@ -9,23 +9,51 @@ use crate::nightly::{self, Request};
 mod ext {
     use super::*;
 
+    #[cfg_attr(
+        not(anyhow_no_diagnostic_namespace),
+        diagnostic::on_unimplemented(
+            message = "`{Self}` cannot be used as the source of an `anyhow::Error`",
+            label = "requires `std::error::Error + Send + Sync + 'static`",
+            note = "if `{Self}` implements `Display`, convert it first with `.map_err(anyhow::Error::msg)`",
+        )
+    )]
     pub trait StdError {
         fn ext_context<C>(self, context: C) -> Error
         where
             C: Display + Send + Sync + 'static;
+
+        fn ext_help<H>(self, help: H) -> Error
+        where
+            H: Display + Send + Sync + 'static;
     }
 
+    // An error type that doesn't implement std::error::Error is reported as
+    // not implementing this trait, with the explanation above, rather than
+    // as not implementing std::error::Error.
     #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+    #[cfg_attr(
+        not(anyhow_no_diagnostic_do_not_recommend),
+        diagnostic::do_not_recommend
+    )]
     impl<E> StdError for E
     where
         E: crate::StdError + Send + Sync + 'static,
     {
+        #[track_caller]
         fn ext_context<C>(self, context: C) -> Error
         where
             C: Display + Send + Sync + 'static,
+        {
+            Error::msg_with_source(context, self)
+        }
+
+        #[track_caller]
+        fn ext_help<H>(self, help: H) -> Error
+        where
+            H: Display + Send + Sync + 'static,
         {
             let backtrace = backtrace_if_absent!(&self);
-            Error::construct_from_context(context, self, backtrace)
+            Error::construct_from_std(self, backtrace).help(help)
         }
     }
 
@@ -36,6 +64,13 @@ mod ext {
         {
             self.context(context)
         }
+
+        fn ext_help<H>(self, help: H) -> Error
+        where
+            H: Display + Send + Sync + 'static,
+        {
+            self.help(help)
+        }
     }
 }
 
@@ -43,6 +78,7 @@ impl<T, E> Context<T, E> for Result<T, E>
 where
     E: ext::StdError + Send + Sync + 'static,
 {
+    #[track_caller]
     fn context<C>(self, context: C) -> Result<T, Error>
     where
         C: Display + Send + Sync + 'static,
@@ -55,6 +91,7 @@ where
         }
     }
 
+    #[track_caller]
     fn with_context<C, F>(self, context: F) -> Result<T, Error>
     where
         C: Display + Send + Sync + 'static,
@@ -65,6 +102,18 @@ where
             Err(error) => Err(error.ext_context(context())),
         }
     }
+
+    #[track_caller]
+    fn with_help<H, F>(self, help: F) -> Result<T, Error>
+    where
+        H: Display + Send + Sync + 'static,
+        F: FnOnce() -> H,
+    {
+        match self {
+            Ok(ok) => Ok(ok),
+            Err(error) => Err(error.ext_help(help())),
+        }
+    }
 }
 
 /// ```
@@ -88,6 +137,7 @@ where
 /// }
 /// ```
 impl<T> Context<T, Infallible> for Option<T> {
+    #[track_caller]
     fn context<C>(self, context: C) -> Result<T, Error>
     where
         C: Display + Send + Sync + 'static,
@@ -96,10 +146,11 @@ impl<T> Context<T, Infallible> for Option<T> {
         // backtrace.
         match self {
             Some(ok) => Ok(ok),
-            None => Err(Error::construct_from_display(context, backtrace!())),
+            None => Err(Error::construct_from_display(context, backtrace!(C))),
         }
     }
 
+    #[track_caller]
     fn with_context<C, F>(self, context: F) -> Result<T, Error>
     where
         C: Display + Send + Sync + 'static,
@@ -107,7 +158,23 @@ impl<T> Context<T, Infallible> for Option<T> {
     {
         match self {
             Some(ok) => Ok(ok),
-            None => Err(Error::construct_from_display(context(), backtrace!())),
+            None => Err(Error::construct_from_display(context(), backtrace!(C))),
+        }
+    }
+
+    #[track_caller]
+    fn with_help<H, F>(self, help: F) -> Result<T, Error>
+    where
+        H: Display + Send + Sync + 'static,
+        F: FnOnce() -> H,
+    {
+        match self {
+            Some(ok) => Ok(ok),
+            None => Err(Error::construct_from_display(
+                "called `Option::with_help()` on a `None` value",
+                backtrace!(H),
+            )
+            .help(help())),
         }
     }
 }
@@ -0,0 +1,158 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+// Lines of unchanged context shown around each change.
+const CONTEXT: usize = 3;
+
+// Past this many cells, 256 KiB of them, the longest common subsequence table
+// is not built, and the differing middle of the two texts is shown as removed
+// then added.
+const MAX_TABLE: usize = 1 << 16;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+// A unified diff of the lines of `left` against the lines of `right`,
+// starting with the `--- left` and `+++ right` header.
+pub(crate) fn unified(left: &str, right: &str) -> String {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let ops = diff(&left, &right);
+
+    let mut out = String::from("--- left\n+++ right");
+    let mut i = 0;
+    while i < ops.len() {
+        if ops[i].0 == Op::Equal {
+            i += 1;
+            continue;
+        }
+
+        // Extend the hunk over changes separated by little enough context that
+        // their surrounding context would overlap.
+        let start = i.saturating_sub(CONTEXT);
+        let mut end = i;
+        let mut equal_run = 0;
+        while end < ops.len() {
+            if ops[end].0 == Op::Equal {
+                if equal_run == 2 * CONTEXT {
+                    break;
+                }
+                equal_run += 1;
+            } else {
+                equal_run = 0;
+            }
+            end += 1;
+        }
+        let end = end - equal_run.saturating_sub(CONTEXT);
+
+        let (mut left_start, mut right_start) = (ops[start].1, ops[start].2);
+        let (mut left_len, mut right_len) = (0, 0);
+        for &(op, _, _) in &ops[start..end] {
+            match op {
+                Op::Equal => {
+                    left_len += 1;
+                    right_len += 1;
+                }
+                Op::Delete => left_len += 1,
+                Op::Insert => right_len += 1,
+            }
+        }
+        // Line numbers are 1-based, except that an empty range names the line
+        // before it.
+        if left_len > 0 {
+            left_start += 1;
+        }
+        if right_len > 0 {
+            right_start += 1;
+        }
+        let _ = write!(
+            out,
+            "\n@@ -{},{} +{},{} @@",
+            left_start, left_len, right_start, right_len,
+        );
+
+        for &(op, l, r) in &ops[start..end] {
+            let (sign, line) = match op {
+                Op::Equal => (' ', left[l]),
+                Op::Delete => ('-', left[l]),
+                Op::Insert => ('+', right[r]),
+            };
+            out.push('\n');
+            out.push(sign);
+            out.push_str(line);
+        }
+
+        i = end;
+    }
+    out
+}
+
+// Each entry is the operation together with the index of the line it applies
+// to in left and in right; for an insertion the left index is where the line
+// goes, and likewise the right index of a deletion.
+fn diff(left: &[&str], right: &[&str]) -> Vec<(Op, usize, usize)> {
+    let prefix = left.iter().zip(right).take_while(|(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    let left_mid = &left[prefix..left.len() - suffix];
+    let right_mid = &right[prefix..right.len() - suffix];
+
+    let mut ops = Vec::with_capacity(left.len() + right.len());
+    for i in 0..prefix {
+        ops.push((Op::Equal, i, i));
+    }
+
+    let (n, m) = (left_mid.len(), right_mid.len());
+    if n.saturating_mul(m) > MAX_TABLE {
+        for i in 0..n {
+            ops.push((Op::Delete, prefix + i, prefix));
+        }
+        for j in 0..m {
+            ops.push((Op::Insert, prefix + n, prefix + j));
+        }
+    } else {
+        // lcs[i][j] is the length of the longest common subsequence of
+        // left_mid[i..] and right_mid[j..].
+        let width = m + 1;
+        let mut lcs = vec![0u32; (n + 1) * width];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * width + j] = if left_mid[i] == right_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && left_mid[i] == right_mid[j] {
+                ops.push((Op::Equal, prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if j == m || i < n && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                ops.push((Op::Delete, prefix + i, prefix + j));
+                i += 1;
+            } else {
+                ops.push((Op::Insert, prefix + i, prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    for k in 0..suffix {
+        ops.push((Op::Equal, left.len() - suffix + k, right.len() - suffix + k));
+    }
+    ops
+}
@@ -1,14 +1,137 @@
+//! Settings for the message of a failed [`ensure!`][crate::ensure!]
+//! comparison.
+//!
+//! When the condition of `ensure!` is a comparison like `a == b` and no
+//! message of its own is given, the error message shows the `Debug`
+//! representation of both operands: ``Condition failed: `a == b` (1 vs 2)``.
+//! By default both operands are left out unless each one is at most 40 bytes
+//! long and contains no whitespace. The setters in this module relax that,
+//! and [`set_custom_message`] keeps the operands when `ensure!` is given a
+//! message of its own.
+//!
+//! The settings are process-wide and not part of the public API.
+//!
+//! ```
+//! use anyhow::{ensure, Result};
+//!
+//! fn check(len: usize) -> Result<()> {
+//!     ensure!(len == 0);
+//!     Ok(())
+//! }
+//!
+//! anyhow::ensure::set_max_operand_len(4);
+//! anyhow::ensure::set_truncate(true);
+//!
+//! let error = check(1234567).unwrap_err();
+//! assert_eq!(
+//!     error.to_string(),
+//!     "Condition failed: `len == 0` (1234\u{2026} (truncated) vs 0)",
+//! );
+//! ```
+
+use crate::diff;
 use crate::Error;
+use alloc::format;
 use alloc::string::String;
+use alloc::vec::Vec;
 use core::fmt::{self, Debug, Write};
-use core::mem::MaybeUninit;
-use core::ptr;
-use core::slice;
-use core::str;
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+static MAX_OPERAND_LEN: AtomicUsize = AtomicUsize::new(40);
+static TRUNCATE: AtomicBool = AtomicBool::new(false);
+static MULTILINE: AtomicBool = AtomicBool::new(false);
+static CUSTOM_MESSAGE: AtomicU8 = AtomicU8::new(0);
+
+/// What `ensure!` does with the operands of a failed comparison when it is
+/// given a message of its own, as in `ensure!(a == b, "checksum mismatch")`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CustomMessage {
+    /// The message alone: `checksum mismatch`. This is the default.
+    Alone,
+    /// The operands follow the message the same way they would follow the
+    /// condition: `checksum mismatch (31 vs 42)`. When an error value is given
+    /// rather than a message, this attaches a note like `Note` does.
+    Inline,
+    /// The message alone, with a note attached that says what the message
+    /// would have been without it: ``Condition failed: `a == b` (31 vs 42)``.
+    /// See [`Error::note`].
+    Note,
+}
+
+/// Set the maximum length in bytes of the `Debug` representation of each
+/// operand. The default is 40.
+pub fn set_max_operand_len(len: usize) {
+    MAX_OPERAND_LEN.store(len, Ordering::Relaxed);
+}
+
+/// Set whether an operand longer than the maximum is cut short and marked
+/// `(truncated)`, rather than leaving both operands out of the message. This
+/// is off by default.
+pub fn set_truncate(truncate: bool) {
+    TRUNCATE.store(truncate, Ordering::Relaxed);
+}
+
+/// Set whether operands whose `Debug` representation contains whitespace or
+/// is over the maximum length are pretty-printed with `{:#?}` on lines of
+/// their own below the condition, rather than left out of the message. The
+/// maximum length does not apply to them. This is off by default.
+///
+/// ```text
+/// Condition failed: `config == expected`
+///   left: Config {
+///     name: "a b",
+/// }
+///  right: Config {
+///     name: "a c",
+/// }
+/// ```
+pub fn set_multiline(multiline: bool) {
+    MULTILINE.store(multiline, Ordering::Relaxed);
+}
+
+/// Set what `ensure!` does with the operands of a failed comparison when it is
+/// given a message of its own. The default is [`CustomMessage::Alone`].
+///
+/// An error value given in place of a message keeps its own `Display`, so
+/// [`CustomMessage::Inline`] attaches a note to it the same way as
+/// [`CustomMessage::Note`].
+pub fn set_custom_message(custom: CustomMessage) {
+    let custom = match custom {
+        CustomMessage::Alone => 0,
+        CustomMessage::Inline => 1,
+        CustomMessage::Note => 2,
+    };
+    CUSTOM_MESSAGE.store(custom, Ordering::Relaxed);
+}
+
+/// The maximum length of an operand currently in effect.
+pub fn max_operand_len() -> usize {
+    MAX_OPERAND_LEN.load(Ordering::Relaxed)
+}
+
+/// Whether operands over the maximum length are truncated.
+pub fn truncate() -> bool {
+    TRUNCATE.load(Ordering::Relaxed)
+}
+
+/// Whether operands containing whitespace are shown on lines of their own.
+pub fn multiline() -> bool {
+    MULTILINE.load(Ordering::Relaxed)
+}
+
+/// What happens to the operands of a comparison with a custom message.
+pub fn custom_message() -> CustomMessage {
+    match CUSTOM_MESSAGE.load(Ordering::Relaxed) {
+        1 => CustomMessage::Inline,
+        2 => CustomMessage::Note,
+        _ => CustomMessage::Alone,
+    }
+}
 
 #[doc(hidden)]
 pub trait BothDebug {
     fn __dispatch_ensure(self, msg: &'static str) -> Error;
+    fn __ensure_operands(self) -> Operands;
 }
 
 impl<A, B> BothDebug for (A, B)
@@ -19,92 +142,544 @@ where
     fn __dispatch_ensure(self, msg: &'static str) -> Error {
         render(msg, &self.0, &self.1)
     }
+
+    fn __ensure_operands(self) -> Operands {
+        Operands::new(Some((&self.0, &self.1)))
+    }
 }
 
 #[doc(hidden)]
 pub trait NotBothDebug {
     fn __dispatch_ensure(self, msg: &'static str) -> Error;
+    fn __ensure_operands(self) -> Operands;
 }
 
 impl<A, B> NotBothDebug for &(A, B) {
     fn __dispatch_ensure(self, msg: &'static str) -> Error {
         Error::msg(msg)
     }
+
+    fn __ensure_operands(self) -> Operands {
+        Operands::new(None)
+    }
+}
+
+#[doc(hidden)]
+pub trait EqText {
+    fn __dispatch_ensure_eq(self, msg: &'static str) -> Error;
+    fn __ensure_eq_values(self) -> EqValues;
+}
+
+impl<A, B> EqText for (&A, &B)
+where
+    A: AsRef<str> + Debug,
+    B: AsRef<str> + Debug,
+{
+    fn __dispatch_ensure_eq(self, msg: &'static str) -> Error {
+        self.__ensure_eq_values().text(format_args!("{}", msg))
+    }
+
+    fn __ensure_eq_values(self) -> EqValues {
+        EqValues::new(self.0, self.1, Some((self.0.as_ref(), self.1.as_ref())))
+    }
+}
+
+#[doc(hidden)]
+pub trait EqDebug {
+    fn __dispatch_ensure_eq(self, msg: &'static str) -> Error;
+    fn __ensure_eq_values(self) -> EqValues;
+}
+
+impl<A, B> EqDebug for &(&A, &B)
+where
+    A: Debug,
+    B: Debug,
+{
+    fn __dispatch_ensure_eq(self, msg: &'static str) -> Error {
+        self.__ensure_eq_values().text(format_args!("{}", msg))
+    }
+
+    fn __ensure_eq_values(self) -> EqValues {
+        EqValues::new(self.0, self.1, None)
+    }
+}
+
+#[doc(hidden)]
+pub trait ValueDebug<'a> {
+    fn __ensure_value(self) -> Option<&'a dyn Debug>;
 }
 
-struct Buf {
-    bytes: [MaybeUninit<u8>; 40],
-    written: usize,
+impl<'a, T> ValueDebug<'a> for (&'a T,)
+where
+    T: Debug,
+{
+    fn __ensure_value(self) -> Option<&'a dyn Debug> {
+        Some(self.0)
+    }
 }
 
-impl Buf {
-    fn new() -> Self {
-        Buf {
-            bytes: [MaybeUninit::uninit(); 40],
-            written: 0,
+#[doc(hidden)]
+pub trait ValueNotDebug<'a> {
+    fn __ensure_value(self) -> Option<&'a dyn Debug>;
+}
+
+impl<'a, T> ValueNotDebug<'a> for &(&'a T,) {
+    fn __ensure_value(self) -> Option<&'a dyn Debug> {
+        None
+    }
+}
+
+// The Debug representation of an operand, cut off at the maximum length
+// without formatting the rest of it.
+struct Operand {
+    string: String,
+    max: usize,
+    // Stop formatting at the first whitespace, which rules out showing the
+    // operand inline.
+    compact: bool,
+    truncated: bool,
+    failed: bool,
+}
+
+impl Operand {
+    fn new(value: &dyn Debug, pretty: bool, max: usize) -> Self {
+        let mut operand = Operand {
+            string: String::new(),
+            max,
+            compact: false,
+            truncated: false,
+            failed: false,
+        };
+        let result = if pretty {
+            write!(operand, "{:#?}", value)
+        } else {
+            write!(operand, "{:?}", value)
+        };
+        operand.failed = result.is_err() && !operand.truncated;
+        operand
+    }
+
+    // The operand if its Debug representation contains no whitespace.
+    fn compact(value: &dyn Debug, max: usize) -> Option<Self> {
+        let mut operand = Operand {
+            string: String::new(),
+            max,
+            compact: true,
+            truncated: false,
+            failed: false,
+        };
+        let result = write!(operand, "{:?}", value);
+        operand.failed = result.is_err() && !operand.truncated;
+        if operand.compact {
+            Some(operand)
+        } else {
+            None
         }
     }
 
-    fn as_str(&self) -> &str {
-        unsafe {
-            str::from_utf8_unchecked(slice::from_raw_parts(
-                self.bytes.as_ptr().cast::<u8>(),
-                self.written,
-            ))
+    fn render(&self, truncate: bool) -> Option<String> {
+        if self.failed || self.truncated && !truncate {
+            return None;
         }
+        let mut rendered = self.string.clone();
+        if self.truncated {
+            rendered.push_str("\u{2026} (truncated)");
+        }
+        Some(rendered)
     }
 }
 
-impl Write for Buf {
+impl Write for Operand {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        if s.bytes().any(|b| b == b' ' || b == b'\n') {
+        if self.compact && s.bytes().any(|b| b == b' ' || b == b'\n') {
+            self.compact = false;
             return Err(fmt::Error);
         }
 
-        let remaining = self.bytes.len() - self.written;
-        if s.len() > remaining {
-            return Err(fmt::Error);
+        let remaining = self.max - self.string.len();
+        if s.len() <= remaining {
+            self.string.push_str(s);
+            return Ok(());
         }
 
-        unsafe {
-            ptr::copy_nonoverlapping(
-                s.as_ptr(),
-                self.bytes.as_mut_ptr().add(self.written).cast::<u8>(),
-                s.len(),
-            );
+        let mut end = remaining;
+        while !s.is_char_boundary(end) {
+            end -= 1;
         }
-        self.written += s.len();
-        Ok(())
+        self.string.push_str(&s[..end]);
+        self.truncated = true;
+        Err(fmt::Error)
     }
 }
 
 fn render(msg: &'static str, lhs: &dyn Debug, rhs: &dyn Debug) -> Error {
-    let mut lhs_buf = Buf::new();
-    if fmt::write(&mut lhs_buf, format_args!("{:?}", lhs)).is_ok() {
-        let mut rhs_buf = Buf::new();
-        if fmt::write(&mut rhs_buf, format_args!("{:?}", rhs)).is_ok() {
-            let lhs_str = lhs_buf.as_str();
-            let rhs_str = rhs_buf.as_str();
+    match render_operands(msg, lhs, rhs) {
+        Some(string) => Error::msg(string),
+        None => Error::msg(msg),
+    }
+}
+
+// The message followed by the operands, if they can be shown.
+fn render_operands(msg: &str, lhs: &dyn Debug, rhs: &dyn Debug) -> Option<String> {
+    let max = max_operand_len();
+    let truncate = truncate();
+
+    // The right operand is not formatted if the left one already has
+    // whitespace.
+    let compact = Operand::compact(lhs, max)
+        .and_then(|lhs_operand| Some((lhs_operand, Operand::compact(rhs, max)?)));
+    if let Some((lhs_operand, rhs_operand)) = compact {
+        if let (Some(lhs_str), Some(rhs_str)) =
+            (lhs_operand.render(truncate), rhs_operand.render(truncate))
+        {
             // "{msg} ({lhs} vs {rhs})"
             let len = msg.len() + 2 + lhs_str.len() + 4 + rhs_str.len() + 1;
             let mut string = String::with_capacity(len);
             string.push_str(msg);
             string.push_str(" (");
-            string.push_str(lhs_str);
+            string.push_str(&lhs_str);
             string.push_str(" vs ");
-            string.push_str(rhs_str);
+            string.push_str(&rhs_str);
             string.push(')');
-            return Error::msg(string);
+            return Some(string);
+        }
+    }
+
+    // Lines of their own leave room for operands of any length.
+    if multiline() {
+        let lhs_operand = Operand::new(lhs, true, usize::MAX);
+        let rhs_operand = Operand::new(rhs, true, usize::MAX);
+        if let (Some(lhs_str), Some(rhs_str)) =
+            (lhs_operand.render(truncate), rhs_operand.render(truncate))
+        {
+            // "{msg}\n  left: {lhs}\n right: {rhs}"
+            let len = msg.len() + 9 + lhs_str.len() + 9 + rhs_str.len();
+            let mut string = String::with_capacity(len);
+            string.push_str(msg);
+            string.push_str("\n  left: ");
+            string.push_str(&lhs_str);
+            string.push_str("\n right: ");
+            string.push_str(&rhs_str);
+            return Some(string);
+        }
+    }
+
+    None
+}
+
+// The operands of a failed comparison that has a custom message. They are
+// rendered while still borrowed, so that the message may take them by value.
+#[doc(hidden)]
+pub struct Operands {
+    custom: CustomMessage,
+    // What follows the message to show the operands, if they can be shown.
+    rendered: Option<String>,
+}
+
+impl Operands {
+    #[cold]
+    fn new(operands: Option<(&dyn Debug, &dyn Debug)>) -> Self {
+        let custom = custom_message();
+        let rendered = match custom {
+            CustomMessage::Alone => None,
+            CustomMessage::Inline | CustomMessage::Note => {
+                operands.and_then(|(lhs, rhs)| render_operands("", lhs, rhs))
+            }
+        };
+        Operands { custom, rendered }
+    }
+
+    #[cold]
+    pub fn text(self, text: fmt::Arguments, msg: &'static str) -> Error {
+        match self.custom {
+            CustomMessage::Alone => crate::__private::format_err(text),
+            CustomMessage::Inline => {
+                let mut text = alloc::fmt::format(text);
+                if let Some(rendered) = &self.rendered {
+                    text.push_str(rendered);
+                }
+                Error::msg(text)
+            }
+            CustomMessage::Note => self.note(crate::__private::format_err(text), msg),
+        }
+    }
+
+    #[cold]
+    pub fn error(self, error: Error, msg: &'static str) -> Error {
+        match self.custom {
+            CustomMessage::Alone => error,
+            CustomMessage::Inline | CustomMessage::Note => self.note(error, msg),
+        }
+    }
+
+    // Attaches what the message would have been without a custom one.
+    fn note(self, error: Error, msg: &'static str) -> Error {
+        match self.rendered {
+            Some(rendered) => error.note(format!("{}{}", msg, rendered)),
+            None => error.note(msg),
+        }
+    }
+}
+
+// The values of a failed ensure_eq!, rendered as " ({lhs} vs {rhs})" if both
+// fit on one line within the maximum length, otherwise as "\n{diff}" with a
+// unified diff of the lines of the two strings, or of their pretty Debug
+// representations if they are not strings. They follow the condition, or the
+// message given in its place.
+#[doc(hidden)]
+pub struct EqValues {
+    rendered: String,
+}
+
+impl EqValues {
+    #[cold]
+    fn new(lhs: &dyn Debug, rhs: &dyn Debug, text: Option<(&str, &str)>) -> Self {
+        let max = max_operand_len();
+        let lhs_operand = Operand::new(lhs, false, max);
+        let rhs_operand = Operand::new(rhs, false, max);
+        let text_is_short = text.map_or(true, |(lhs, rhs)| {
+            !lhs.contains('\n') && !rhs.contains('\n')
+        });
+        if let (Some(lhs_str), Some(rhs_str), true) = (
+            lhs_operand.render(false),
+            rhs_operand.render(false),
+            text_is_short,
+        ) {
+            if !lhs_str.contains('\n') && !rhs_str.contains('\n') {
+                let rendered = format!(" ({} vs {})", lhs_str, rhs_str);
+                return EqValues { rendered };
+            }
+        }
+
+        let (lhs, rhs) = match text {
+            Some((lhs, rhs)) => (String::from(lhs), String::from(rhs)),
+            None => (format!("{:#?}", lhs), format!("{:#?}", rhs)),
+        };
+        let rendered = if lhs == rhs {
+            // Unequal values with the same representation, like NaN.
+            format!("\n  both: {}", lhs)
+        } else {
+            format!("\n{}", diff::unified(&lhs, &rhs))
+        };
+        EqValues { rendered }
+    }
+
+    #[cold]
+    pub fn text(self, text: fmt::Arguments) -> Error {
+        Error::msg(format!("{}{}", text, self.rendered))
+    }
+
+    // An error value keeps its own Display, so the values go in a note.
+    #[cold]
+    pub fn error(self, error: Error, msg: &'static str) -> Error {
+        error.note(format!("{}{}", msg, self.rendered))
+    }
+}
+
+// "{msg} ({lhs} vs {rhs})" or, for operands too long for that,
+// "{msg}\n  both: {value}".
+#[doc(hidden)]
+#[cold]
+pub fn ensure_ne_failed(msg: &'static str, lhs: &dyn Debug, rhs: &dyn Debug) -> Error {
+    let max = max_operand_len();
+
+    let lhs_operand = Operand::new(lhs, false, max);
+    let rhs_operand = Operand::new(rhs, false, max);
+    if let (Some(lhs_str), Some(rhs_str)) = (lhs_operand.render(false), rhs_operand.render(false)) {
+        return Error::msg(format!("{} ({} vs {})", msg, lhs_str, rhs_str));
+    }
+
+    match Operand::new(lhs, true, max).render(truncate()) {
+        Some(value) => Error::msg(format!("{}\n  both: {}", msg, value)),
+        None => Error::msg(msg),
+    }
+}
+
+// "{msg} ({value})" if the value is short and contains no whitespace,
+// otherwise the value is attached as a note.
+#[doc(hidden)]
+#[cold]
+pub fn ensure_match_failed(msg: &'static str, value: Option<&dyn Debug>) -> Error {
+    if let Some(value) = value {
+        if let Some(operand) = Operand::compact(value, max_operand_len()) {
+            if let Some(value) = operand.render(truncate()) {
+                return Error::msg(format!("{} ({})", msg, value));
+            }
+        }
+    }
+    note_value(Error::msg(msg), value)
+}
+
+#[doc(hidden)]
+#[cold]
+pub fn note_value(error: Error, value: Option<&dyn Debug>) -> Error {
+    match value {
+        Some(value) => error.note(format!("value: {:#?}", value)),
+        None => error,
+    }
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn to_f64<T>(value: &T) -> f64
+where
+    T: Into<f64> + Copy,
+{
+    (*value).into()
+}
+
+// Within the absolute tolerance, or within the relative tolerance of the larger
+// magnitude. Equal values, including equal infinities, are always close, and
+// unequal values of which one is infinite or NaN never are.
+#[doc(hidden)]
+pub fn approx_eq(lhs: f64, rhs: f64, abs: Option<f64>, rel: Option<f64>) -> bool {
+    if lhs == rhs {
+        return true;
+    }
+    if !lhs.is_finite() || !rhs.is_finite() {
+        return false;
+    }
+    let diff = magnitude(lhs - rhs);
+    abs.map_or(false, |abs| diff <= abs)
+        || rel.map_or(false, |rel| {
+            diff <= rel * magnitude(lhs).max(magnitude(rhs))
+        })
+}
+
+// f64::abs, which is only available in core from Rust 1.85.
+fn magnitude(x: f64) -> f64 {
+    if x < 0.0 {
+        -x
+    } else {
+        x
+    }
+}
+
+// "{msg}\n     left: {lhs}\n    right: {rhs}" followed by the absolute and
+// relative difference for each tolerance that was given.
+#[doc(hidden)]
+#[cold]
+pub fn ensure_approx_failed(
+    msg: &'static str,
+    lhs: &dyn Debug,
+    rhs: &dyn Debug,
+    lhs_f64: f64,
+    rhs_f64: f64,
+    abs: Option<f64>,
+    rel: Option<f64>,
+) -> Error {
+    let diff = magnitude(lhs_f64 - rhs_f64);
+    let mut string = format!("{}\n     left: {:?}\n    right: {:?}", msg, lhs, rhs);
+    if let Some(abs) = abs {
+        let _ = write!(string, "\n abs diff: {:?} > {:?}", diff, abs);
+    }
+    if let Some(rel) = rel {
+        let rel_diff = diff / magnitude(lhs_f64).max(magnitude(rhs_f64));
+        let _ = write!(string, "\n rel diff: {:?} > {:?}", rel_diff, rel);
+    }
+    Error::msg(string)
+}
+
+// "{msg}\n  {expr} = {value}" for each operand that was evaluated. Operands
+// that are not Debug, whose value reads the same as their source code, like
+// literals, or that were already listed are left out.
+#[doc(hidden)]
+pub fn render_values(msg: &'static str, values: &[(&'static str, Option<&dyn Debug>)]) -> Error {
+    let mut rendered = Values::new();
+    rendered.push(values);
+    rendered.render(msg)
+}
+
+// The values of the operands of the false terms of an `||` condition, rendered
+// as each term turns out false so that no borrow of an operand is held while
+// the next term runs.
+#[doc(hidden)]
+pub struct Values {
+    string: String,
+    listed: Vec<&'static str>,
+}
+
+impl Values {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Values {
+            string: String::new(),
+            listed: Vec::new(),
+        }
+    }
+
+    #[cold]
+    pub fn push(&mut self, values: &[(&'static str, Option<&dyn Debug>)]) {
+        let max = max_operand_len();
+        let truncate = truncate();
+
+        for &(expr, value) in values {
+            let Some(value) = value else {
+                continue;
+            };
+            if self.listed.contains(&expr) {
+                continue;
+            }
+            let Some(rendered) = Operand::new(value, false, max).render(truncate) else {
+                continue;
+            };
+            if rendered == expr {
+                continue;
+            }
+            self.listed.push(expr);
+            self.string.push_str("\n  ");
+            self.string.push_str(expr);
+            self.string.push_str(" = ");
+            self.string.push_str(&rendered);
+        }
+    }
+
+    #[cold]
+    pub fn render(mut self, msg: &'static str) -> Error {
+        if self.string.is_empty() {
+            Error::msg(msg)
+        } else {
+            self.string.insert_str(0, msg);
+            Error::msg(self.string)
         }
     }
-    Error::msg(msg)
 }
 
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __parse_ensure {
-    (atom () $bail:tt $fuel:tt {($($rhs:tt)+) ($($lhs:tt)+) $op:tt} $dup:tt $(,)?) => {
-        $crate::__fancy_ensure!($($lhs)+, $op, $($rhs)+)
+    (atom () $bail:tt $fuel:tt {($($rhs:tt)+) ($($lhs:tt)+) &&} $dup:tt $(,)?) => {
+        $crate::__power_ensure!(@parse $bail () [] ($($lhs)+) && ($($rhs)+))
+    };
+
+    (atom () $bail:tt $fuel:tt {($($rhs:tt)+) ($($lhs:tt)+) ||} $dup:tt $(,)?) => {
+        $crate::__power_ensure!(@parse $bail () [] ($($lhs)+) || ($($rhs)+))
+    };
+
+    (atom () ($ret:ident $($bail:tt)*) $fuel:tt {($($rhs:tt)+) ($($lhs:tt)+) $op:tt} $dup:tt $(,)?) => {
+        $crate::__fancy_ensure!($ret, $($lhs)+, $op, $($rhs)+)
+    };
+
+    (atom () ($($bail:tt)*) $fuel:tt {($($rhs:tt)+) ($($lhs:tt)+) &&} $dup:tt , $($arg:tt)+) => {
+        $crate::__fallback_ensure!($($bail)*)
+    };
+
+    (atom () ($($bail:tt)*) $fuel:tt {($($rhs:tt)+) ($($lhs:tt)+) ||} $dup:tt , $($arg:tt)+) => {
+        $crate::__fallback_ensure!($($bail)*)
+    };
+
+    (atom () ($ret:ident $($bail:tt)*) $fuel:tt {($($rhs:tt)+) ($($lhs:tt)+) $op:tt} $dup:tt , $($arg:tt)+) => {
+        $crate::__fancy_ensure!($ret, $($lhs)+, $op, $($rhs)+, $($arg)+)
+    };
+
+    (atom () $bail:tt $fuel:tt {($($last:tt)+) $($parse:tt)+} $dup:tt $(,)?) => {
+        $crate::__power_ensure!(@parse $bail () [] $($parse)+ ($($last)+))
+    };
+
+    // missing condition
+
+    (0 () ($ret:ident) $fuel:tt {()} ()) => {
+        $crate::__missing_ensure!()
     };
 
     // low precedence control flow constructs
@@ -820,6 +1395,16 @@ macro_rules! __parse_ensure {
         $crate::__parse_ensure!(0 $stack $bail ($($fuel)*) {($($buf)* $shr) $($parse)*} ($($rest)*) $($rest)*)
     };
 
+    // logical operators joining the top level operands
+
+    (atom () $bail:tt (~$($fuel:tt)*) {($($buf:tt)+) $($parse:tt)*} ($and:tt $($dup:tt)*) && $($rest:tt)*) => {
+        $crate::__parse_ensure!(0 () $bail ($($fuel)*) {() $($parse)* ($($buf)*) $and} ($($rest)*) $($rest)*)
+    };
+
+    (atom () $bail:tt (~$($fuel:tt)*) {($($buf:tt)+) $($parse:tt)*} ($or:tt $($dup:tt)*) || $($rest:tt)*) => {
+        $crate::__parse_ensure!(0 () $bail ($($fuel)*) {() $($parse)* ($($buf)*) $or} ($($rest)*) $($rest)*)
+    };
+
     // low precedence binary operators
 
     (atom ($($stack:tt)+) $bail:tt (~$($fuel:tt)*) {($($buf:tt)*) $($parse:tt)*} ($and:tt $($dup:tt)*) && $($rest:tt)*) => {
@@ -884,22 +1469,88 @@ macro_rules! __parse_ensure {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __fancy_ensure {
-    ($lhs:expr, $op:tt, $rhs:expr) => {
+    (@cond_msg $cond:expr, $($arg:tt)*) => {
+        $crate::__private::concat!("Condition failed: `", $crate::__private::stringify!($cond), "`")
+    };
+    (@msg $lhs:expr, $op:tt, $rhs:expr) => {
+        $crate::__private::concat!(
+            "Condition failed: `",
+            $crate::__private::stringify!($lhs),
+            " ",
+            $crate::__private::stringify!($op),
+            " ",
+            $crate::__private::stringify!($rhs),
+            "`",
+        )
+    };
+    ($ret:ident, $lhs:expr, $op:tt, $rhs:expr, $msg:literal $(,)?) => {{
+        let operands = match (&$lhs, &$rhs) {
+            (lhs, rhs) => {
+                if !(lhs $op rhs) {
+                    #[allow(unused_imports)]
+                    use $crate::__private::{BothDebug, NotBothDebug};
+                    $crate::__private::Some((lhs, rhs).__ensure_operands())
+                } else {
+                    $crate::__private::None
+                }
+            }
+        };
+        // The operands are no longer borrowed when the message is evaluated.
+        if let $crate::__private::Some(operands) = operands {
+            return $crate::__private::$ret(operands.text(
+                $crate::__private::format_args!($msg),
+                $crate::__fancy_ensure!(@msg $lhs, $op, $rhs),
+            ));
+        }
+    }};
+    ($ret:ident, $lhs:expr, $op:tt, $rhs:expr, $fmt:literal, $($arg:tt)*) => {{
+        let operands = match (&$lhs, &$rhs) {
+            (lhs, rhs) => {
+                if !(lhs $op rhs) {
+                    #[allow(unused_imports)]
+                    use $crate::__private::{BothDebug, NotBothDebug};
+                    $crate::__private::Some((lhs, rhs).__ensure_operands())
+                } else {
+                    $crate::__private::None
+                }
+            }
+        };
+        // The operands are no longer borrowed when the message is evaluated.
+        if let $crate::__private::Some(operands) = operands {
+            return $crate::__private::$ret(operands.text(
+                $crate::__private::format_args!($fmt, $($arg)*),
+                $crate::__fancy_ensure!(@msg $lhs, $op, $rhs),
+            ));
+        }
+    }};
+    ($ret:ident, $lhs:expr, $op:tt, $rhs:expr, $($arg:tt)+) => {{
+        let operands = match (&$lhs, &$rhs) {
+            (lhs, rhs) => {
+                if !(lhs $op rhs) {
+                    #[allow(unused_imports)]
+                    use $crate::__private::{BothDebug, NotBothDebug};
+                    $crate::__private::Some((lhs, rhs).__ensure_operands())
+                } else {
+                    $crate::__private::None
+                }
+            }
+        };
+        // The operands are no longer borrowed when the message is evaluated.
+        if let $crate::__private::Some(operands) = operands {
+            return $crate::__private::$ret(operands.error(
+                $crate::__anyhow!($($arg)+),
+                $crate::__fancy_ensure!(@msg $lhs, $op, $rhs),
+            ));
+        }
+    }};
+    ($ret:ident, $lhs:expr, $op:tt, $rhs:expr) => {
         match (&$lhs, &$rhs) {
             (lhs, rhs) => {
                 if !(lhs $op rhs) {
                     #[allow(unused_imports)]
                     use $crate::__private::{BothDebug, NotBothDebug};
-                    return Err((lhs, rhs).__dispatch_ensure(
-                        $crate::__private::concat!(
-                            "Condition failed: `",
-                            $crate::__private::stringify!($lhs),
-                            " ",
-                            $crate::__private::stringify!($op),
-                            " ",
-                            $crate::__private::stringify!($rhs),
-                            "`",
-                        ),
+                    return $crate::__private::$ret((lhs, rhs).__dispatch_ensure(
+                        $crate::__fancy_ensure!(@msg $lhs, $op, $rhs),
                     ));
                 }
             }
@@ -907,29 +1558,186 @@ macro_rules! __fancy_ensure {
     };
 }
 
+// Evaluates a condition made of operands, or comparisons of two operands,
+// joined by only `&&` or only `||`, and on failure lists the value of each
+// operand that was evaluated. Evaluation short-circuits the same way as the
+// condition written out would.
 #[doc(hidden)]
 #[macro_export]
-macro_rules! __fallback_ensure {
+macro_rules! __power_ensure {
+    // sort the flat list of operands and operators from __parse_ensure into
+    // terms
+
+    (@parse $bail:tt $logic:tt [$($terms:tt)*] ($($lhs:tt)+) == ($($rhs:tt)+) $($rest:tt)*) => {
+        $crate::__power_ensure!(@logic $bail $logic [$($terms)* [cmp ($($lhs)+) ($crate::__private::stringify!($($lhs)+)) == ($($rhs)+) ($crate::__private::stringify!($($rhs)+))]] $($rest)*)
+    };
+
+    (@parse $bail:tt $logic:tt [$($terms:tt)*] ($($lhs:tt)+) != ($($rhs:tt)+) $($rest:tt)*) => {
+        $crate::__power_ensure!(@logic $bail $logic [$($terms)* [cmp ($($lhs)+) ($crate::__private::stringify!($($lhs)+)) != ($($rhs)+) ($crate::__private::stringify!($($rhs)+))]] $($rest)*)
+    };
+
+    (@parse $bail:tt $logic:tt [$($terms:tt)*] ($($lhs:tt)+) < ($($rhs:tt)+) $($rest:tt)*) => {
+        $crate::__power_ensure!(@logic $bail $logic [$($terms)* [cmp ($($lhs)+) ($crate::__private::stringify!($($lhs)+)) < ($($rhs)+) ($crate::__private::stringify!($($rhs)+))]] $($rest)*)
+    };
+
+    (@parse $bail:tt $logic:tt [$($terms:tt)*] ($($lhs:tt)+) <= ($($rhs:tt)+) $($rest:tt)*) => {
+        $crate::__power_ensure!(@logic $bail $logic [$($terms)* [cmp ($($lhs)+) ($crate::__private::stringify!($($lhs)+)) <= ($($rhs)+) ($crate::__private::stringify!($($rhs)+))]] $($rest)*)
+    };
+
+    (@parse $bail:tt $logic:tt [$($terms:tt)*] ($($lhs:tt)+) > ($($rhs:tt)+) $($rest:tt)*) => {
+        $crate::__power_ensure!(@logic $bail $logic [$($terms)* [cmp ($($lhs)+) ($crate::__private::stringify!($($lhs)+)) > ($($rhs)+) ($crate::__private::stringify!($($rhs)+))]] $($rest)*)
+    };
+
+    (@parse $bail:tt $logic:tt [$($terms:tt)*] ($($lhs:tt)+) >= ($($rhs:tt)+) $($rest:tt)*) => {
+        $crate::__power_ensure!(@logic $bail $logic [$($terms)* [cmp ($($lhs)+) ($crate::__private::stringify!($($lhs)+)) >= ($($rhs)+) ($crate::__private::stringify!($($rhs)+))]] $($rest)*)
+    };
+
+    (@parse $bail:tt $logic:tt [$($terms:tt)*] ($($operand:tt)+) $($rest:tt)*) => {
+        $crate::__power_ensure!(@logic $bail $logic [$($terms)* [atom ($($operand)+) ($crate::__private::stringify!($($operand)+))]] $($rest)*)
+    };
+
+    (@logic $bail:tt () $terms:tt && $($rest:tt)+) => {
+        $crate::__power_ensure!(@parse $bail (&&) $terms $($rest)+)
+    };
+
+    (@logic $bail:tt (&&) $terms:tt && $($rest:tt)+) => {
+        $crate::__power_ensure!(@parse $bail (&&) $terms $($rest)+)
+    };
+
+    (@logic $bail:tt () $terms:tt || $($rest:tt)+) => {
+        $crate::__power_ensure!(@parse $bail (||) $terms $($rest)+)
+    };
+
+    (@logic $bail:tt (||) $terms:tt || $($rest:tt)+) => {
+        $crate::__power_ensure!(@parse $bail (||) $terms $($rest)+)
+    };
+
+    (@logic $bail:tt (&&) [$($terms:tt)*]) => {{
+        $crate::__power_ensure! { @and $bail $($terms)* }
+    }};
+
+    (@logic $bail:tt (||) [$($terms:tt)*]) => {{
+        let mut values = $crate::__private::Values::new();
+        $crate::__power_ensure! { @or $bail values $($terms)* }
+    }};
+
+    // a single operand, a chained comparison, or a mix of && and ||
+    (@logic ($($bail:tt)*) $($rest:tt)*) => {
+        $crate::__fallback_ensure!($($bail)*)
+    };
+
+    // fail as soon as one term is false, with the values of that term; each
+    // term's borrows end before the next term runs
+
+    (@and $bail:tt [atom ($($operand:tt)+) $name:tt] $($rest:tt)*) => {
+        {
+            let value = &($($operand)+);
+            if $crate::__private::not(value) {
+                $crate::__power_ensure!(@fail $bail [($name value)])
+            }
+        }
+        $crate::__power_ensure! { @and $bail $($rest)* }
+    };
+
+    (@and $bail:tt [cmp ($($lhs:tt)+) $lhs_name:tt $op:tt ($($rhs:tt)+) $rhs_name:tt] $($rest:tt)*) => {
+        {
+            let lhs = &($($lhs)+);
+            let rhs = &($($rhs)+);
+            if !(lhs $op rhs) {
+                $crate::__power_ensure!(@fail $bail [($lhs_name lhs) ($rhs_name rhs)])
+            }
+        }
+        $crate::__power_ensure! { @and $bail $($rest)* }
+    };
+
+    (@and $bail:tt) => {};
+
+    // fail once every term is false, with the values of all terms, which are
+    // rendered to $values as each term turns out false
+
+    (@or $bail:tt $values:ident [atom ($($operand:tt)+) $name:tt] $($rest:tt)*) => {
+        let failed = {
+            let value = &($($operand)+);
+            let failed = $crate::__private::not(value);
+            if failed {
+                #[allow(unused_imports)]
+                use $crate::__private::{ValueDebug, ValueNotDebug};
+                $values.push(&[($name, (value,).__ensure_value())]);
+            }
+            failed
+        };
+        if failed {
+            $crate::__power_ensure! { @or $bail $values $($rest)* }
+        }
+    };
+
+    (@or $bail:tt $values:ident [cmp ($($lhs:tt)+) $lhs_name:tt $op:tt ($($rhs:tt)+) $rhs_name:tt] $($rest:tt)*) => {
+        let failed = {
+            let lhs = &($($lhs)+);
+            let rhs = &($($rhs)+);
+            let failed = !(lhs $op rhs);
+            if failed {
+                #[allow(unused_imports)]
+                use $crate::__private::{ValueDebug, ValueNotDebug};
+                $values.push(&[($lhs_name, (lhs,).__ensure_value()), ($rhs_name, (rhs,).__ensure_value())]);
+            }
+            failed
+        };
+        if failed {
+            $crate::__power_ensure! { @or $bail $values $($rest)* }
+        }
+    };
+
+    (@or ($ret:ident $($bail:tt)*) $values:ident) => {
+        return $crate::__private::$ret($values.render($crate::__power_ensure!(@msg $($bail)*)));
+    };
+
+    (@fail ($ret:ident $($bail:tt)*) [$(($name:tt $value:ident))*]) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{ValueDebug, ValueNotDebug};
+        return $crate::__private::$ret($crate::__private::render_values(
+            $crate::__power_ensure!(@msg $($bail)*),
+            &[$(($name, ($value,).__ensure_value())),*],
+        ));
+    }};
+
+    (@msg $cond:expr $(,)?) => {
+        $crate::__private::concat!("Condition failed: `", $crate::__private::stringify!($cond), "`")
+    };
+}
+
+// Invoked without tokens, to report the missing condition of `ensure!()`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __missing_ensure {
     ($cond:expr $(,)?) => {
+        $crate::__fallback_ensure!(Err $cond)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fallback_ensure {
+    ($ret:ident $cond:expr $(,)?) => {
         if $crate::__private::not($cond) {
-            return $crate::__private::Err($crate::Error::msg(
+            return $crate::__private::$ret($crate::Error::msg(
                 $crate::__private::concat!("Condition failed: `", $crate::__private::stringify!($cond), "`")
             ));
         }
     };
-    ($cond:expr, $msg:literal $(,)?) => {
+    ($ret:ident $cond:expr, $msg:literal $(,)?) => {
         if $crate::__private::not($cond) {
-            return $crate::__private::Err($crate::__anyhow!($msg));
+            return $crate::__private::$ret($crate::__anyhow!($msg));
         }
     };
-    ($cond:expr, $err:expr $(,)?) => {
+    ($ret:ident $cond:expr, $err:expr $(,)?) => {
         if $crate::__private::not($cond) {
-            return $crate::__private::Err($crate::__anyhow!($err));
+            return $crate::__private::$ret($crate::__anyhow!($err));
         }
     };
-    ($cond:expr, $fmt:expr, $($arg:tt)*) => {
+    ($ret:ident $cond:expr, $fmt:expr, $($arg:tt)*) => {
         if $crate::__private::not($cond) {
-            return $crate::__private::Err($crate::__anyhow!($fmt, $($arg)*));
+            return $crate::__private::$ret($crate::__anyhow!($fmt, $($arg)*));
         }
     };
 }
@@ -1,12 +1,14 @@
+use crate::attachment::{Attachments, SectionKind};
 use crate::backtrace::Backtrace;
+#[cfg(feature = "std")]
+use crate::backtrace::{Backtraces, Frames};
 use crate::chain::Chain;
 #[cfg(error_generic_member_access)]
 use crate::nightly::{self, Request};
-#[cfg(any(feature = "std", not(anyhow_no_core_error)))]
-use crate::ptr::Mut;
-use crate::ptr::{Own, Ref};
-use crate::{Error, StdError};
+use crate::ptr::{Mut, Own, Ref};
+use crate::{Error, Severity, StdError};
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::any::TypeId;
 use core::fmt::{self, Debug, Display};
 use core::mem::ManuallyDrop;
@@ -15,6 +17,8 @@ use core::ops::{Deref, DerefMut};
 use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr;
 use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::backtrace::BacktraceStatus;
 
 impl Error {
     /// Create a new error object from any error type.
@@ -27,6 +31,7 @@ impl Error {
     #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
     #[cold]
     #[must_use]
+    #[track_caller]
     pub fn new<E>(error: E) -> Self
     where
         E: StdError + Send + Sync + 'static,
@@ -74,11 +79,56 @@ impl Error {
     /// ```
     #[cold]
     #[must_use]
+    #[track_caller]
     pub fn msg<M>(message: M) -> Self
     where
         M: Display + Debug + Send + Sync + 'static,
     {
-        Error::construct_from_adhoc(message, backtrace!())
+        Error::construct_from_adhoc(message, backtrace!(M))
+    }
+
+    /// Create a new error object from a printable error message and the error
+    /// that caused it.
+    ///
+    /// The message is displayed as the error, and `source` is returned by its
+    /// `source()`, so `source` and its own causes appear in
+    /// [`chain`][Error::chain] and [`root_cause`][Error::root_cause], and the
+    /// new error can be downcast to the type of either the message or the
+    /// source. This is the same error as `Error::new(source).context(message)`,
+    /// in the order it reads, and is what
+    /// <code>[anyhow!]\(source = err, "...")</code> and
+    /// <code>[bail!]\(source = err, "...")</code> construct.
+    ///
+    /// [anyhow!]: crate::anyhow
+    /// [bail!]: crate::bail
+    ///
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io;
+    /// #
+    /// let source = io::Error::new(io::ErrorKind::NotFound, "no such file");
+    /// let error = Error::msg_with_source("failed to open config.toml", source);
+    ///
+    /// assert_eq!(error.to_string(), "failed to open config.toml");
+    /// assert_eq!(error.root_cause().to_string(), "no such file");
+    /// assert!(error.downcast_ref::<io::Error>().is_some());
+    /// ```
+    #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+    #[cold]
+    #[must_use]
+    #[track_caller]
+    pub fn msg_with_source<M, E>(message: M, source: E) -> Self
+    where
+        M: Display + Send + Sync + 'static,
+        E: StdError + Send + Sync + 'static,
+    {
+        let backtrace = backtrace_if_absent!(&source);
+        #[cfg(not(feature = "min-code-size"))]
+        return Error::construct_from_context(message, source, backtrace);
+        // One vtable per message type instead of one per pair of message type
+        // and error type, at the cost of a second allocation.
+        #[cfg(feature = "min-code-size")]
+        return Error::construct_from_std(source, backtrace).context(message);
     }
 
     /// Construct an error object from a type-erased standard library error.
@@ -137,6 +187,7 @@ impl Error {
     #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
     #[cold]
     #[must_use]
+    #[track_caller]
     pub fn from_boxed(boxed_error: Box<dyn StdError + Send + Sync + 'static>) -> Self {
         let backtrace = backtrace_if_absent!(&*boxed_error);
         Error::construct_from_boxed(boxed_error, backtrace)
@@ -155,8 +206,13 @@ impl Error {
             object_reallocate_boxed: object_reallocate_boxed::<E>,
             object_downcast: object_downcast::<E>,
             object_drop_rest: object_drop_front::<E>,
-            #[cfg(all(not(error_generic_member_access), feature = "std"))]
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
             object_backtrace: no_backtrace,
+            object_inner: no_inner,
         };
 
         // Safety: passing vtable that operates on the right type E.
@@ -169,6 +225,11 @@ impl Error {
         M: Display + Debug + Send + Sync + 'static,
     {
         use crate::wrapper::MessageError;
+        #[cfg(feature = "min-code-size")]
+        let message = match crate::wrapper::StrMessage::new(message) {
+            Ok(message) => return Error::construct_from_str_message(message, backtrace),
+            Err(message) => message,
+        };
         let error: MessageError<M> = MessageError(message);
         let vtable = &ErrorVTable {
             object_drop: object_drop::<MessageError<M>>,
@@ -179,8 +240,13 @@ impl Error {
             object_reallocate_boxed: object_reallocate_boxed::<MessageError<M>>,
             object_downcast: object_downcast::<M>,
             object_drop_rest: object_drop_front::<M>,
-            #[cfg(all(not(error_generic_member_access), feature = "std"))]
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
             object_backtrace: no_backtrace,
+            object_inner: no_inner,
         };
 
         // Safety: MessageError is repr(transparent) so it is okay for the
@@ -188,6 +254,39 @@ impl Error {
         unsafe { Error::construct(error, vtable, backtrace) }
     }
 
+    // One vtable for messages that are &'static str or String, instead of one
+    // for each.
+    #[cfg(feature = "min-code-size")]
+    #[cold]
+    fn construct_from_str_message(
+        message: crate::wrapper::StrMessage,
+        backtrace: Option<Backtrace>,
+    ) -> Self {
+        use crate::wrapper::{MessageError, StrMessage};
+        let error: MessageError<StrMessage> = MessageError(message);
+        let vtable = &ErrorVTable {
+            object_drop: object_drop::<MessageError<StrMessage>>,
+            object_ref: object_ref::<MessageError<StrMessage>>,
+            #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+            object_boxed: object_boxed::<MessageError<StrMessage>>,
+            #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+            object_reallocate_boxed: object_reallocate_boxed::<MessageError<StrMessage>>,
+            object_downcast: str_message_downcast,
+            object_drop_rest: object_drop_front::<StrMessage>,
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
+            object_backtrace: no_backtrace,
+            object_inner: no_inner,
+        };
+
+        // Safety: MessageError is repr(transparent) so it is okay for the
+        // vtable to allow casting the MessageError<StrMessage> to StrMessage.
+        unsafe { Error::construct(error, vtable, backtrace) }
+    }
+
     #[cold]
     pub(crate) fn construct_from_display<M>(message: M, backtrace: Option<Backtrace>) -> Self
     where
@@ -204,8 +303,13 @@ impl Error {
             object_reallocate_boxed: object_reallocate_boxed::<DisplayError<M>>,
             object_downcast: object_downcast::<M>,
             object_drop_rest: object_drop_front::<M>,
-            #[cfg(all(not(error_generic_member_access), feature = "std"))]
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
             object_backtrace: no_backtrace,
+            object_inner: no_inner,
         };
 
         // Safety: DisplayError is repr(transparent) so it is okay for the
@@ -213,7 +317,10 @@ impl Error {
         unsafe { Error::construct(error, vtable, backtrace) }
     }
 
-    #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+    #[cfg(all(
+        any(feature = "std", not(anyhow_no_core_error)),
+        not(feature = "min-code-size"),
+    ))]
     #[cold]
     pub(crate) fn construct_from_context<C, E>(
         context: C,
@@ -235,8 +342,13 @@ impl Error {
             object_reallocate_boxed: object_reallocate_boxed::<ContextError<C, E>>,
             object_downcast: context_downcast::<C, E>,
             object_drop_rest: context_drop_rest::<C, E>,
-            #[cfg(all(not(error_generic_member_access), feature = "std"))]
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
             object_backtrace: no_backtrace,
+            object_inner: no_inner,
         };
 
         // Safety: passing vtable that operates on the right type.
@@ -260,8 +372,13 @@ impl Error {
             object_reallocate_boxed: object_reallocate_boxed::<BoxedError>,
             object_downcast: object_downcast::<Box<dyn StdError + Send + Sync>>,
             object_drop_rest: object_drop_front::<Box<dyn StdError + Send + Sync>>,
-            #[cfg(all(not(error_generic_member_access), feature = "std"))]
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
             object_backtrace: no_backtrace,
+            object_inner: no_inner,
         };
 
         // Safety: BoxedError is repr(transparent) so it is okay for the vtable
@@ -280,12 +397,25 @@ impl Error {
         vtable: &'static ErrorVTable,
         backtrace: Option<Backtrace>,
     ) -> Self
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        let attachments = new_attachments(backtrace);
+        unsafe { Error::construct_with_attachments(error, vtable, attachments) }
+    }
+
+    #[cold]
+    unsafe fn construct_with_attachments<E>(
+        error: E,
+        vtable: &'static ErrorVTable,
+        attachments: Attachments,
+    ) -> Self
     where
         E: StdError + Send + Sync + 'static,
     {
         let inner: Box<ErrorImpl<E>> = Box::new(ErrorImpl {
             vtable,
-            backtrace,
+            attachments,
             _object: error,
         });
         // Erase the concrete type of E from the compile-time type system. This
@@ -358,6 +488,12 @@ impl Error {
     where
         C: Display + Send + Sync + 'static,
     {
+        #[cfg(feature = "min-code-size")]
+        let context = match crate::wrapper::StrMessage::new(context) {
+            Ok(context) => return self.str_context(context),
+            Err(context) => context,
+        };
+
         let error: ContextError<C, Error> = ContextError {
             context,
             error: self,
@@ -372,15 +508,234 @@ impl Error {
             object_reallocate_boxed: object_reallocate_boxed::<ContextError<C, Error>>,
             object_downcast: context_chain_downcast::<C>,
             object_drop_rest: context_chain_drop_rest::<C>,
-            #[cfg(all(not(error_generic_member_access), feature = "std"))]
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
             object_backtrace: context_backtrace::<C>,
+            object_inner: context_chain_inner::<C>,
         };
 
-        // As the cause is anyhow::Error, we already have a backtrace for it.
-        let backtrace = None;
+        // As the cause is anyhow::Error, we already have a backtrace for it,
+        // as well as any custom trace.
+        let attachments = Attachments::new();
 
         // Safety: passing vtable that operates on the right type.
-        unsafe { Error::construct(error, vtable, backtrace) }
+        unsafe { Error::construct_with_attachments(error, vtable, attachments) }
+    }
+
+    // One vtable for context that is &'static str or String, instead of one
+    // for each.
+    #[cfg(feature = "min-code-size")]
+    #[cold]
+    fn str_context(self, context: crate::wrapper::StrMessage) -> Self {
+        use crate::wrapper::StrMessage;
+        let error: ContextError<StrMessage, Error> = ContextError {
+            context,
+            error: self,
+        };
+
+        let vtable = &ErrorVTable {
+            object_drop: object_drop::<ContextError<StrMessage, Error>>,
+            object_ref: object_ref::<ContextError<StrMessage, Error>>,
+            #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+            object_boxed: object_boxed::<ContextError<StrMessage, Error>>,
+            #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+            object_reallocate_boxed: object_reallocate_boxed::<ContextError<StrMessage, Error>>,
+            object_downcast: str_context_chain_downcast,
+            object_drop_rest: str_context_chain_drop_rest,
+            #[cfg(all(
+                not(error_generic_member_access),
+                feature = "std",
+                not(feature = "no-backtrace"),
+            ))]
+            object_backtrace: context_backtrace::<StrMessage>,
+            object_inner: context_chain_inner::<StrMessage>,
+        };
+
+        let attachments = Attachments::new();
+
+        // Safety: passing vtable that operates on the right type.
+        unsafe { Error::construct_with_attachments(error, vtable, attachments) }
+    }
+
+    /// Attach a note to the error.
+    ///
+    /// Notes, as well as [help][Error::help] and [suggestion][Error::suggestion]
+    /// sections, are not part of the error's `Display` representation nor of
+    /// its chain of source errors. They are printed by the `Debug`
+    /// representation after the list of causes, which is the representation
+    /// you get when returning the error from `fn main`.
+    ///
+    /// ```
+    /// use anyhow::{anyhow, Result};
+    ///
+    /// fn write_output(force: bool) -> Result<()> {
+    ///     if !force {
+    ///         return Err(anyhow!("failed to write output.txt")
+    ///             .note("output.txt already exists")
+    ///             .help("run with --force to overwrite"));
+    ///     }
+    ///     # const IGNORE: &str = stringify! {
+    ///     ...
+    ///     # };
+    ///     # Ok(())
+    /// }
+    /// ```
+    ///
+    /// ```console
+    /// Error: failed to write output.txt
+    ///
+    /// note: output.txt already exists
+    /// help: run with --force to overwrite
+    /// ```
+    ///
+    /// Sections attached to an error remain in place after context is added
+    /// to it. They are printed outermost first, in the order they were
+    /// attached.
+    #[cold]
+    #[must_use]
+    pub fn note<N>(self, note: N) -> Self
+    where
+        N: Display + Send + Sync + 'static,
+    {
+        self.section(SectionKind::Note, Box::new(note))
+    }
+
+    /// Attach a help section to the error.
+    ///
+    /// Refer to [`note`][Error::note] for how sections are rendered.
+    #[cold]
+    #[must_use]
+    pub fn help<H>(self, help: H) -> Self
+    where
+        H: Display + Send + Sync + 'static,
+    {
+        self.section(SectionKind::Help, Box::new(help))
+    }
+
+    /// Attach a suggestion to the error.
+    ///
+    /// Refer to [`note`][Error::note] for how sections are rendered.
+    #[cold]
+    #[must_use]
+    pub fn suggestion<S>(self, suggestion: S) -> Self
+    where
+        S: Display + Send + Sync + 'static,
+    {
+        self.section(SectionKind::Suggestion, Box::new(suggestion))
+    }
+
+    fn section(mut self, kind: SectionKind, message: Box<dyn Display + Send + Sync>) -> Self {
+        let attachments = unsafe { ErrorImpl::attachments_mut(self.inner.by_mut()) };
+        attachments.push_section(kind, message);
+        self
+    }
+
+    /// Attach a stable error code to the error.
+    ///
+    /// Codes give support teams and users something greppable to refer to,
+    /// independent of the wording of the error message. The same can be done
+    /// with the `code = ...` argument of [`anyhow!`][crate::anyhow] and
+    /// [`bail!`][crate::bail].
+    ///
+    /// ```
+    /// use anyhow::{anyhow, bail, Result};
+    ///
+    /// fn check_lockfile(stale: bool) -> Result<()> {
+    ///     if stale {
+    ///         bail!(code = "E0142", "lockfile is out of date");
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// let error = check_lockfile(true).unwrap_err();
+    /// assert_eq!(error.code(), Some("E0142"));
+    ///
+    /// let error = anyhow!("lockfile is out of date").with_code("E0142");
+    /// assert_eq!(error.code(), Some("E0142"));
+    /// ```
+    ///
+    /// In the `Debug` representation, the code is printed in front of the
+    /// error message.
+    ///
+    /// ```console
+    /// Error: [E0142] lockfile is out of date
+    /// ```
+    #[cold]
+    #[must_use]
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        let attachments = unsafe { ErrorImpl::attachments_mut(self.inner.by_mut()) };
+        attachments.set_code(code);
+        self
+    }
+
+    /// The error code attached to this error, if any.
+    ///
+    /// This searches the context chain from the outermost context inward, so
+    /// that a code attached to an error remains available after context is
+    /// added to it, and a code attached to a context takes precedence over the
+    /// code of the error it wraps.
+    ///
+    /// Use a [`Catalog`][crate::Catalog] to look up the long-form explanation
+    /// of the code.
+    pub fn code(&self) -> Option<&'static str> {
+        unsafe { ErrorImpl::code(self.inner.by_ref()) }
+    }
+
+    /// Set how serious the error is.
+    ///
+    /// The same can be done with the `severity = ...` argument of
+    /// [`anyhow!`][crate::anyhow] and [`bail!`][crate::bail].
+    ///
+    /// ```
+    /// use anyhow::{anyhow, Context, Result, Severity};
+    ///
+    /// fn load_cache() -> Result<()> {
+    ///     # const IGNORE: &str = stringify! {
+    ///     ...
+    ///     # };
+    ///     Err(anyhow!(severity = Severity::Warning, "cache is corrupt, ignoring"))
+    /// }
+    ///
+    /// let error = load_cache().context("failed to start").unwrap_err();
+    /// assert_eq!(error.severity(), Severity::Warning);
+    /// ```
+    #[cold]
+    #[must_use]
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        let attachments = unsafe { ErrorImpl::attachments_mut(self.inner.by_mut()) };
+        attachments.set_severity(severity);
+        self
+    }
+
+    /// How serious the error is.
+    ///
+    /// This is the highest severity attached anywhere in the context chain, or
+    /// [`Severity::Error`] if none was attached.
+    pub fn severity(&self) -> Severity {
+        unsafe { ErrorImpl::severity(self.inner.by_ref()) }
+    }
+
+    /// Set the process exit code to use if this error is returned from `main`
+    /// through [`anyhow::ExitResult`][crate::ExitResult].
+    ///
+    /// Like an [error code][Error::code], the exit code remains attached when
+    /// context is added to the error, and the outermost one wins.
+    #[cold]
+    #[must_use]
+    pub fn with_exit_code(mut self, exit_code: u8) -> Self {
+        let attachments = unsafe { ErrorImpl::attachments_mut(self.inner.by_mut()) };
+        attachments.set_exit_code(exit_code);
+        self
+    }
+
+    /// The exit code attached with [`with_exit_code`][Error::with_exit_code],
+    /// if any.
+    pub fn exit_code(&self) -> Option<u8> {
+        unsafe { ErrorImpl::layers(self.inner.by_ref()) }
+            .find_map(|layer| unsafe { ErrorImpl::attachments(layer) }.exit_code())
     }
 
     /// Get the backtrace for this Error.
@@ -398,6 +753,9 @@ impl Error {
     /// - If you want only panics to have backtraces, set `RUST_BACKTRACE=1` and
     ///   `RUST_LIB_BACKTRACE=0`.
     ///
+    /// Capturing can also be enabled or disabled per error type with a
+    /// [`backtrace::Policy`][crate::backtrace::Policy].
+    ///
     /// # Stability
     ///
     /// Standard library backtraces are only available when using Rust &ge;
@@ -415,6 +773,54 @@ impl Error {
         unsafe { ErrorImpl::backtrace(self.inner.by_ref()) }
     }
 
+    /// The frames of this error's backtrace, parsed into function names and
+    /// source locations. Empty if no backtrace was captured.
+    ///
+    /// See [`backtrace::Frames`][crate::backtrace::Frames].
+    #[cfg(feature = "std")]
+    pub fn frames(&self) -> Frames {
+        Frames::new(self.backtrace())
+    }
+
+    /// An iterator of every distinct backtrace captured in the chain of source
+    /// errors, such as when this error wraps a boxed error that was itself
+    /// created from an `anyhow::Error`.
+    ///
+    /// Each item is a backtrace paired with the index in [`chain`] of the
+    /// cause it belongs to. Only backtraces with a status of `Captured` are
+    /// included. A backtrace that is exposed by several causes, as happens
+    /// with context, is reported once, for the innermost of them.
+    ///
+    /// Backtraces are found in the layers of context added through anyhow,
+    /// including those of an `anyhow::Error` that was converted into a
+    /// `Box<dyn std::error::Error>` and wrapped by another error. Backtraces
+    /// carried by other error types are found when building with a nightly
+    /// compiler, where errors can provide their backtrace through
+    /// `core::error::Request`.
+    ///
+    /// [`chain`]: Error::chain
+    ///
+    /// ```
+    /// # use anyhow::anyhow;
+    /// #
+    /// # let error = anyhow!("...");
+    /// let causes: Vec<_> = error.chain().collect();
+    /// for (i, backtrace) in error.backtraces() {
+    ///     println!("{}\n{}", causes[i], backtrace);
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn backtraces(&self) -> Backtraces {
+        Backtraces::new(unsafe { ErrorImpl::backtraces(self.inner.by_ref()) })
+    }
+
+    /// Get the custom trace captured for this Error, if a capturer was
+    /// registered with [`backtrace::set_capturer`][crate::backtrace::set_capturer]
+    /// at the time the error was created.
+    pub fn trace(&self) -> Option<&(dyn Display + Send + Sync + 'static)> {
+        unsafe { ErrorImpl::trace(self.inner.by_ref()) }
+    }
+
     /// An iterator of the chain of source errors contained by this Error.
     ///
     /// This iterator will visit every error in the cause chain of this error
@@ -602,6 +1008,14 @@ impl Error {
     #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
     #[must_use]
     pub fn into_boxed_dyn_error(self) -> Box<dyn StdError + Send + Sync + 'static> {
+        #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+        if unsafe { ErrorImpl::layers(self.inner.by_ref()) }.any(|layer| {
+            unsafe { ErrorImpl::attachments(layer) }
+                .backtrace()
+                .is_some()
+        }) {
+            return Box::new(BoxedWithBacktrace(self));
+        }
         let outer = ManuallyDrop::new(self);
         unsafe {
             // Use vtable to attach ErrorImpl<E>'s native StdError vtable for
@@ -676,6 +1090,7 @@ where
     E: StdError + Send + Sync + 'static,
 {
     #[cold]
+    #[track_caller]
     fn from(error: E) -> Self {
         let backtrace = backtrace_if_absent!(&error);
         Error::construct_from_std(error, backtrace)
@@ -728,8 +1143,13 @@ struct ErrorVTable {
     object_reallocate_boxed: unsafe fn(Own<ErrorImpl>) -> Box<dyn StdError + Send + Sync + 'static>,
     object_downcast: unsafe fn(Ref<ErrorImpl>, TypeId) -> Option<Ref<()>>,
     object_drop_rest: unsafe fn(Own<ErrorImpl>, TypeId),
-    #[cfg(all(not(error_generic_member_access), feature = "std"))]
+    #[cfg(all(
+        not(error_generic_member_access),
+        feature = "std",
+        not(feature = "no-backtrace"),
+    ))]
     object_backtrace: unsafe fn(Ref<ErrorImpl>) -> Option<&Backtrace>,
+    object_inner: unsafe fn(Ref<ErrorImpl>) -> Option<Ref<ErrorImpl>>,
 }
 
 // Safety: requires layout of *e to match ErrorImpl<E>.
@@ -804,14 +1224,54 @@ where
     }
 }
 
-#[cfg(all(not(error_generic_member_access), feature = "std"))]
+#[cfg(all(
+    not(error_generic_member_access),
+    feature = "std",
+    not(feature = "no-backtrace"),
+))]
 fn no_backtrace(e: Ref<ErrorImpl>) -> Option<&Backtrace> {
     let _ = e;
     None
 }
 
+fn no_inner(e: Ref<ErrorImpl>) -> Option<Ref<ErrorImpl>> {
+    let _ = e;
+    None
+}
+
+// Not generic over the error type, so that this part of constructing an error
+// is compiled once rather than once per vtable.
+#[cold]
+#[inline(never)]
+fn new_attachments(backtrace: Option<Backtrace>) -> Attachments {
+    let mut attachments = Attachments::new();
+    if let Some(trace) = crate::backtrace::capture_trace() {
+        attachments.set_trace(trace);
+    }
+    #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+    if crate::backtrace::take_sampled() {
+        attachments.set_sampled();
+    }
+
+    // Only a captured backtrace is worth its space in the attachments. An
+    // error without one reports a disabled backtrace.
+    #[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+    if let Some(backtrace) = backtrace {
+        if let BacktraceStatus::Captured = backtrace.status() {
+            attachments.set_backtrace(backtrace);
+        }
+    }
+    #[cfg(not(all(feature = "std", not(feature = "no-backtrace"))))]
+    let _ = backtrace;
+
+    attachments
+}
+
 // Safety: requires layout of *e to match ErrorImpl<ContextError<C, E>>.
-#[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+#[cfg(all(
+    any(feature = "std", not(anyhow_no_core_error)),
+    not(feature = "min-code-size"),
+))]
 unsafe fn context_downcast<C, E>(e: Ref<ErrorImpl>, target: TypeId) -> Option<Ref<()>>
 where
     C: 'static,
@@ -831,7 +1291,10 @@ where
 }
 
 // Safety: requires layout of *e to match ErrorImpl<ContextError<C, E>>.
-#[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+#[cfg(all(
+    any(feature = "std", not(anyhow_no_core_error)),
+    not(feature = "min-code-size"),
+))]
 unsafe fn context_drop_rest<C, E>(e: Own<ErrorImpl>, target: TypeId)
 where
     C: 'static,
@@ -888,8 +1351,53 @@ where
     }
 }
 
+// Safety: requires layout of *e to match ErrorImpl<StrMessage>.
+#[cfg(feature = "min-code-size")]
+unsafe fn str_message_downcast(e: Ref<ErrorImpl>, target: TypeId) -> Option<Ref<()>> {
+    let unerased_ref = e.cast::<ErrorImpl<crate::wrapper::StrMessage>>();
+    let message = unsafe { ptr::addr_of!((*unerased_ref.as_ptr())._object) };
+    unsafe { crate::wrapper::StrMessage::downcast(message, target) }.map(Ref::from_raw)
+}
+
+// Safety: requires layout of *e to match
+// ErrorImpl<ContextError<StrMessage, Error>>.
+#[cfg(feature = "min-code-size")]
+unsafe fn str_context_chain_downcast(e: Ref<ErrorImpl>, target: TypeId) -> Option<Ref<()>> {
+    use crate::wrapper::StrMessage;
+    let unerased_ref = e.cast::<ErrorImpl<ContextError<StrMessage, Error>>>();
+    let context = unsafe { ptr::addr_of!((*unerased_ref.as_ptr())._object.context) };
+    match unsafe { StrMessage::downcast(context, target) } {
+        Some(context) => Some(Ref::from_raw(context)),
+        // StrMessage itself is never the target, so this recurses.
+        None => unsafe { context_chain_downcast::<StrMessage>(e, target) },
+    }
+}
+
+// Safety: requires layout of *e to match
+// ErrorImpl<ContextError<StrMessage, Error>>.
+#[cfg(feature = "min-code-size")]
+unsafe fn str_context_chain_drop_rest(e: Own<ErrorImpl>, target: TypeId) {
+    use crate::wrapper::StrMessage;
+    let unerased_ref = e
+        .by_ref()
+        .cast::<ErrorImpl<ContextError<StrMessage, Error>>>();
+    let context = unsafe { ptr::addr_of!((*unerased_ref.as_ptr())._object.context) };
+    if unsafe { StrMessage::downcast(context, target) }.is_some() {
+        // The string was taken out of the context. Drop the rest of the data
+        // structure rooted in the next Error.
+        let unerased_own = e.cast::<ErrorImpl<ContextError<ManuallyDrop<StrMessage>, Error>>>();
+        drop(unsafe { unerased_own.boxed() });
+    } else {
+        unsafe { context_chain_drop_rest::<StrMessage>(e, target) };
+    }
+}
+
 // Safety: requires layout of *e to match ErrorImpl<ContextError<C, Error>>.
-#[cfg(all(not(error_generic_member_access), feature = "std"))]
+#[cfg(all(
+    not(error_generic_member_access),
+    feature = "std",
+    not(feature = "no-backtrace"),
+))]
 #[allow(clippy::unnecessary_wraps)]
 unsafe fn context_backtrace<C>(e: Ref<ErrorImpl>) -> Option<&Backtrace>
 where
@@ -901,18 +1409,43 @@ where
     Some(backtrace)
 }
 
+// Safety: requires layout of *e to match ErrorImpl<ContextError<C, Error>>.
+unsafe fn context_chain_inner<C>(e: Ref<ErrorImpl>) -> Option<Ref<ErrorImpl>>
+where
+    C: 'static,
+{
+    let unerased_ref = e.cast::<ErrorImpl<ContextError<C, Error>>>();
+    let unerased = unsafe { unerased_ref.deref() };
+    Some(unerased._object.error.inner.by_ref())
+}
+
+// Reported by errors that were created without capturing a backtrace, which
+// therefore have none stored.
+#[cfg(feature = "std")]
+static DISABLED_BACKTRACE: Backtrace = Backtrace::disabled();
+
 // NOTE: If working with `ErrorImpl<()>`, references should be avoided in favor
 // of raw pointers and `NonNull`.
 // repr C to ensure that E remains in the final position.
 #[repr(C)]
 pub(crate) struct ErrorImpl<E = ()> {
     vtable: &'static ErrorVTable,
-    backtrace: Option<Backtrace>,
+    // Holds the backtrace too, so that an error without any attachments and
+    // without a captured backtrace is only two words plus E.
+    attachments: Attachments,
     // NOTE: Don't use directly. Use only through vtable. Erased type may have
     // different alignment.
     _object: E,
 }
 
+// Identifies the vtable of every layer of an error, so that tests can count
+// how many distinct vtables a set of errors was built from.
+pub(crate) fn vtable_addrs(error: &Error) -> Vec<usize> {
+    unsafe { ErrorImpl::layers(error.inner.by_ref()) }
+        .map(|layer| unsafe { vtable(layer.ptr) } as *const ErrorVTable as usize)
+        .collect()
+}
+
 // Reads the vtable out of `p`. This is the same as `p.as_ref().vtable`, but
 // avoids converting `p` into a reference.
 unsafe fn vtable(p: NonNull<ErrorImpl>) -> &'static ErrorVTable {
@@ -957,33 +1490,139 @@ impl ErrorImpl {
 
     #[cfg(feature = "std")]
     pub(crate) unsafe fn backtrace(this: Ref<Self>) -> &Backtrace {
-        // This unwrap can only panic if the underlying error's backtrace method
-        // is nondeterministic, which would only happen in maliciously
-        // constructed code.
-        unsafe { this.deref() }
-            .backtrace
-            .as_ref()
+        unsafe { Self::own_backtrace(this) }
             .or_else(|| {
                 #[cfg(error_generic_member_access)]
                 return nightly::request_ref_backtrace(unsafe { Self::error(this) });
-                #[cfg(not(error_generic_member_access))]
+                #[cfg(all(not(error_generic_member_access), not(feature = "no-backtrace")))]
                 return unsafe { (vtable(this.ptr).object_backtrace)(this) };
+                #[cfg(all(not(error_generic_member_access), feature = "no-backtrace"))]
+                return None;
             })
-            .expect("backtrace capture failed")
+            .unwrap_or(&DISABLED_BACKTRACE)
+    }
+
+    // The backtrace stored in this ErrorImpl itself, as opposed to one
+    // provided by the underlying error.
+    #[cfg(feature = "std")]
+    unsafe fn own_backtrace(this: Ref<Self>) -> Option<&Backtrace> {
+        #[cfg(not(feature = "no-backtrace"))]
+        return unsafe { Self::attachments(this) }.backtrace();
+        #[cfg(feature = "no-backtrace")]
+        return {
+            let _ = this;
+            None
+        };
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) unsafe fn backtraces(this: Ref<Self>) -> Vec<(usize, &Backtrace)> {
+        let mut found = Vec::new();
+        // The ErrorImpl of each layer of context is the source of the layer
+        // before it, so its position in the chain is its depth.
+        for (depth, layer) in unsafe { Self::layers(this) }.enumerate() {
+            if let Some(backtrace) = unsafe { Self::own_backtrace(layer) } {
+                found.push((depth, backtrace));
+            }
+        }
+        // The same for the layers of an anyhow::Error that was boxed and is
+        // the source of some error in the chain.
+        #[cfg(not(feature = "no-backtrace"))]
+        for (depth, cause) in unsafe { Self::chain(this) }.enumerate() {
+            if let Some(boxed) = cause.downcast_ref::<BoxedWithBacktrace>() {
+                for (i, layer) in unsafe { Self::layers(boxed.0.inner.by_ref()) }.enumerate() {
+                    if let Some(backtrace) = unsafe { Self::own_backtrace(layer) } {
+                        found.push((depth + i, backtrace));
+                    }
+                }
+            }
+        }
+        #[cfg(error_generic_member_access)]
+        for (depth, cause) in unsafe { Self::chain(this) }.enumerate() {
+            if let Some(backtrace) = nightly::request_ref_backtrace(cause) {
+                found.push((depth, backtrace));
+            }
+        }
+
+        // Attribute each backtrace to the innermost cause providing it.
+        let mut distinct: Vec<(usize, &Backtrace)> = Vec::with_capacity(found.len());
+        for &(depth, backtrace) in &found {
+            let captured = matches!(backtrace.status(), BacktraceStatus::Captured);
+            let deeper = found
+                .iter()
+                .any(|&(other_depth, other)| other_depth > depth && ptr::eq(other, backtrace));
+            let seen = distinct.iter().any(|&(_, other)| ptr::eq(other, backtrace));
+            if captured && !deeper && !seen {
+                distinct.push((depth, backtrace));
+            }
+        }
+        distinct.sort_by_key(|&(depth, _)| depth);
+        distinct
     }
 
     #[cfg(error_generic_member_access)]
     unsafe fn provide<'a>(this: Ref<'a, Self>, request: &mut Request<'a>) {
-        if let Some(backtrace) = unsafe { &this.deref().backtrace } {
+        if let Some(backtrace) = unsafe { Self::own_backtrace(this) } {
             nightly::provide_ref_backtrace(request, backtrace);
         }
         nightly::provide(unsafe { Self::error(this) }, request);
+        // Only has an effect if nothing above provided a backtrace.
+        nightly::provide_ref_backtrace(request, &DISABLED_BACKTRACE);
     }
 
     #[cold]
     pub(crate) unsafe fn chain(this: Ref<Self>) -> Chain {
         Chain::new(unsafe { Self::error(this) })
     }
+
+    pub(crate) unsafe fn attachments(this: Ref<Self>) -> &Attachments {
+        unsafe { &(*this.ptr.as_ptr()).attachments }
+    }
+
+    unsafe fn attachments_mut(this: Mut<Self>) -> &mut Attachments {
+        unsafe { &mut (*this.ptr.as_ptr()).attachments }
+    }
+
+    pub(crate) unsafe fn code(this: Ref<Self>) -> Option<&'static str> {
+        unsafe { Self::layers(this) }.find_map(|layer| unsafe { Self::attachments(layer) }.code())
+    }
+
+    pub(crate) unsafe fn trace(this: Ref<Self>) -> Option<&(dyn Display + Send + Sync + 'static)> {
+        unsafe { Self::layers(this) }.find_map(|layer| unsafe { Self::attachments(layer) }.trace())
+    }
+
+    // Whether the backtrace was skipped by backtrace::Policy::Sample.
+    #[cfg(feature = "std")]
+    pub(crate) unsafe fn sampled(this: Ref<Self>) -> bool {
+        unsafe { Self::layers(this) }.any(|layer| unsafe { Self::attachments(layer) }.sampled())
+    }
+
+    pub(crate) unsafe fn severity(this: Ref<Self>) -> Severity {
+        unsafe { Self::layers(this) }
+            .filter_map(|layer| unsafe { Self::attachments(layer) }.severity())
+            .max()
+            .unwrap_or_default()
+    }
+
+    // Iterates over this ErrorImpl followed by the ErrorImpls of any
+    // anyhow::Error to which it is attached as context, outermost first.
+    pub(crate) unsafe fn layers(this: Ref<Self>) -> Layers {
+        Layers { next: Some(this) }
+    }
+}
+
+pub(crate) struct Layers<'a> {
+    next: Option<Ref<'a, ErrorImpl>>,
+}
+
+impl<'a> Iterator for Layers<'a> {
+    type Item = Ref<'a, ErrorImpl>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let layer = self.next?;
+        self.next = unsafe { (vtable(layer.ptr).object_inner)(layer) };
+        Some(layer)
+    }
 }
 
 impl<E> StdError for ErrorImpl<E>
@@ -1018,6 +1657,39 @@ where
     }
 }
 
+// An anyhow::Error that carries a backtrace, converted into a boxed trait
+// object. Unlike ErrorImpl<E> this is one type whatever the E, so that
+// Error::backtraces can recognize it by downcasting when it is the source of
+// another error further down the chain.
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+struct BoxedWithBacktrace(Error);
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+impl StdError for BoxedWithBacktrace {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        unsafe { ErrorImpl::error(self.0.inner.by_ref()).source() }
+    }
+
+    #[cfg(error_generic_member_access)]
+    fn provide<'a>(&'a self, request: &mut Request<'a>) {
+        unsafe { ErrorImpl::provide(self.0.inner.by_ref(), request) }
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+impl Debug for BoxedWithBacktrace {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        unsafe { ErrorImpl::debug(self.0.inner.by_ref(), formatter) }
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+impl Display for BoxedWithBacktrace {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        unsafe { Display::fmt(ErrorImpl::error(self.0.inner.by_ref()), formatter) }
+    }
+}
+
 #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
 impl From<Error> for Box<dyn StdError + Send + Sync + 'static> {
     #[cold]
@@ -0,0 +1,153 @@
+use crate::report::{self, Format};
+use crate::{Error, Result};
+use std::eprintln;
+use std::fmt::{self, Debug};
+use std::process::{ExitCode, Termination};
+
+/// Return type for `fn main` that picks the process exit code based on the
+/// error.
+///
+/// Failures are printed to standard error using the error's `Debug`
+/// representation, which can be configured through the [`report`] module.
+///
+/// [`report`]: crate::report
+///
+/// Returning `anyhow::Result<()>` from `main` prints the error's `Debug`
+/// representation and always exits with code 1. With `ExitResult`, the exit
+/// code of a failure is instead the first of:
+///
+/// - the code returned by the mapping function given to
+///   [`exit_code_with`][ExitResult::exit_code_with], if it returns `Some`;
+/// - the code attached to the error with
+///   [`Error::with_exit_code`][crate::Error::with_exit_code];
+/// - a [`std::process::ExitCode`] provided by any error in the chain of
+///   source errors through the standard library's generic member access API
+///   (nightly only);
+/// - 1.
+///
+/// On stable Rust, an `ExitCode` provided by a source error is not found.
+/// Downcast the chain in the mapping function instead:
+///
+/// ```
+/// # use anyhow::ExitResult;
+/// # use std::io;
+/// #
+/// # fn run() -> anyhow::Result<()> {
+/// #     Ok(())
+/// # }
+/// #
+/// fn main() -> ExitResult {
+///     anyhow::main(run).exit_code_with(|error| {
+///         let io_error = error.chain().find_map(|cause| cause.downcast_ref::<io::Error>())?;
+///         match io_error.kind() {
+///             io::ErrorKind::NotFound => Some(66),
+///             io::ErrorKind::PermissionDenied => Some(77),
+///             _ => None,
+///         }
+///     })
+/// }
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use anyhow::{anyhow, ExitResult, Result, Severity};
+///
+/// fn main() -> ExitResult {
+///     anyhow::main(run).exit_code_with(|error| match error.severity() {
+///         Severity::Debug | Severity::Warning => Some(0),
+///         Severity::Error => None,
+///         Severity::Fatal => Some(101),
+///     })
+/// }
+///
+/// fn run() -> Result<()> {
+///     # const IGNORE: &str = stringify! {
+///     ...
+///     # };
+///     # return Ok(());
+///     Err(anyhow!("usage: tool <input>").with_exit_code(2))
+/// }
+/// ```
+#[must_use]
+pub struct ExitResult {
+    result: Result<()>,
+    mapping: Option<fn(&Error) -> Option<u8>>,
+}
+
+/// Run the body of a program and convert its result into an [`ExitResult`].
+///
+/// `fn main() -> ExitResult { anyhow::main(run) }` is equivalent to
+/// `fn main() -> ExitResult { ExitResult::from(run()) }`.
+///
+/// See [`ExitResult`] for how the exit code is picked. On stable Rust, an
+/// [`ExitCode`] provided by a source error in the chain is not found; use
+/// [`exit_code_with`][ExitResult::exit_code_with] to map such errors.
+pub fn main<F>(f: F) -> ExitResult
+where
+    F: FnOnce() -> Result<()>,
+{
+    ExitResult::from(f())
+}
+
+impl ExitResult {
+    /// Determine exit codes with the given function before falling back to
+    /// the error's own exit code.
+    pub fn exit_code_with(mut self, mapping: fn(&Error) -> Option<u8>) -> Self {
+        self.mapping = Some(mapping);
+        self
+    }
+
+    /// The underlying result.
+    pub fn into_result(self) -> Result<()> {
+        self.result
+    }
+}
+
+impl From<Result<()>> for ExitResult {
+    fn from(result: Result<()>) -> Self {
+        ExitResult {
+            result,
+            mapping: None,
+        }
+    }
+}
+
+impl From<Error> for ExitResult {
+    fn from(error: Error) -> Self {
+        ExitResult::from(Err(error))
+    }
+}
+
+impl Termination for ExitResult {
+    fn report(self) -> ExitCode {
+        let error = match &self.result {
+            Ok(()) => return ExitCode::SUCCESS,
+            Err(error) => error,
+        };
+        match report::format() {
+            Format::Full | Format::Oneline => eprintln!("Error: {:?}", error),
+            Format::Json => eprintln!("{:?}", error),
+        }
+
+        let exit_code = self.mapping.and_then(|mapping| mapping(error));
+        if let Some(exit_code) = exit_code.or_else(|| error.exit_code()) {
+            return ExitCode::from(exit_code);
+        }
+
+        #[cfg(error_generic_member_access)]
+        for cause in error.chain() {
+            if let Some(exit_code) = crate::nightly::request_value_exit_code(cause) {
+                return exit_code;
+            }
+        }
+
+        ExitCode::FAILURE
+    }
+}
+
+impl Debug for ExitResult {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&self.result, formatter)
+    }
+}
@@ -1,7 +1,17 @@
+use crate::attachment::SectionKind;
+#[cfg(feature = "std")]
+use crate::backtrace::{Backtrace, Frames};
 use crate::chain::Chain;
 use crate::error::ErrorImpl;
 use crate::ptr::Ref;
-use core::fmt::{self, Debug, Write};
+use crate::report::{self, Format};
+#[cfg(feature = "std")]
+use alloc::format;
+#[cfg(feature = "std")]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Write};
 
 impl ErrorImpl {
     pub(crate) unsafe fn display(this: Ref<Self>, f: &mut fmt::Formatter) -> fmt::Result {
@@ -18,18 +28,32 @@ impl ErrorImpl {
     }
 
     pub(crate) unsafe fn debug(this: Ref<Self>, f: &mut fmt::Formatter) -> fmt::Result {
-        let error = unsafe { Self::error(this) };
-
         if f.alternate() {
-            return Debug::fmt(error, f);
+            return Debug::fmt(unsafe { Self::error(this) }, f);
         }
 
+        match report::format() {
+            Format::Full => unsafe { Self::debug_full(this, f) },
+            Format::Oneline => unsafe { Self::debug_oneline(this, f) },
+            Format::Json => unsafe { Self::debug_json(this, f) },
+        }
+    }
+
+    unsafe fn debug_full(this: Ref<Self>, f: &mut fmt::Formatter) -> fmt::Result {
+        let error = unsafe { Self::error(this) };
+        let color = report::use_color();
+
+        if let Some(code) = unsafe { Self::code(this) } {
+            write!(f, "{} ", Styled(color, RED, format_args!("[{}]", code)))?;
+        }
         write!(f, "{}", error)?;
 
         if let Some(cause) = error.source() {
-            write!(f, "\n\nCaused by:")?;
+            write!(f, "\n\n{}", Styled(color, BOLD, "Caused by:"))?;
             let multiple = cause.source().is_some();
-            for (n, error) in Chain::new(cause).enumerate() {
+            let max_depth = report::max_depth().unwrap_or(usize::MAX);
+            let mut chain = Chain::new(cause);
+            for (n, error) in chain.by_ref().take(max_depth).enumerate() {
                 writeln!(f)?;
                 let mut indented = Indented {
                     inner: f,
@@ -38,32 +62,382 @@ impl ErrorImpl {
                 };
                 write!(indented, "{}", error)?;
             }
+            let rest = chain.len();
+            if rest > 0 {
+                write!(f, "\n    ... {} more", rest)?;
+            }
+        }
+
+        let mut sections = unsafe { Self::layers(this) }
+            .flat_map(|layer| unsafe { Self::attachments(layer) }.sections())
+            .peekable();
+        if sections.peek().is_some() {
+            writeln!(f)?;
+            for section in sections {
+                let label = section.kind.label();
+                let style = match section.kind {
+                    SectionKind::Note => BOLD,
+                    SectionKind::Help | SectionKind::Suggestion => CYAN,
+                };
+                write!(f, "\n{} ", Styled(color, style, format_args!("{}:", label)))?;
+                let mut hanging = HangingIndent {
+                    inner: f,
+                    width: label.len() + 2,
+                };
+                write!(hanging, "{}", section.message)?;
+            }
         }
 
         #[cfg(feature = "std")]
         {
-            use alloc::string::ToString;
-            use std::backtrace::BacktraceStatus;
-
-            let backtrace = unsafe { Self::backtrace(this) };
-            if let BacktraceStatus::Captured = backtrace.status() {
-                let mut backtrace = backtrace.to_string();
-                write!(f, "\n\n")?;
-                if backtrace.starts_with("stack backtrace:") {
-                    // Capitalize to match "Caused by:"
-                    backtrace.replace_range(0..1, "S");
-                } else {
-                    // "stack backtrace:" prefix was removed in
-                    // https://github.com/rust-lang/backtrace-rs/pull/286
-                    writeln!(f, "Stack backtrace:")?;
+            let sampled = unsafe { Self::sampled(this) };
+            if report::all_backtraces() {
+                let multiple = error.source().and_then(|cause| cause.source()).is_some();
+                let backtraces = unsafe { Self::backtraces(this) };
+                if backtraces.is_empty() && sampled {
+                    write_sampled(f, color)?;
                 }
-                backtrace.truncate(backtrace.trim_end().len());
-                write!(f, "{}", backtrace)?;
+                for (depth, backtrace) in backtraces {
+                    let heading = match depth {
+                        0 => String::from("Stack backtrace:"),
+                        n if multiple => format!("Stack backtrace of cause {}:", n - 1),
+                        _ => String::from("Stack backtrace of cause:"),
+                    };
+                    write!(f, "\n\n{}\n", Styled(color, BOLD, heading))?;
+                    write_backtrace(f, &render_backtrace(backtrace), color)?;
+                }
+            } else if let Some(backtrace) = unsafe { Self::captured_backtrace(this) } {
+                let heading = Styled(color, BOLD, "Stack backtrace:");
+                write!(f, "\n\n{}\n", heading)?;
+                write_backtrace(f, &backtrace, color)?;
+            } else if sampled {
+                write_sampled(f, color)?;
             }
         }
 
+        if let Some(trace) = unsafe { Self::trace(this) } {
+            write!(f, "\n\n{}\n{}", Styled(color, BOLD, "Trace:"), trace)?;
+        }
+
         Ok(())
     }
+
+    unsafe fn debug_oneline(this: Ref<Self>, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(code) = unsafe { Self::code(this) } {
+            write!(f, "[{}] ", code)?;
+        }
+
+        let max_depth = report::max_depth().unwrap_or(usize::MAX);
+        let mut chain = unsafe { Self::chain(this) };
+        for (n, error) in chain.by_ref().take(max_depth.saturating_add(1)).enumerate() {
+            if n > 0 {
+                f.write_str(": ")?;
+            }
+            write!(OneLine { inner: f }, "{}", error)?;
+        }
+        let rest = chain.len();
+        if rest > 0 {
+            write!(f, ": ... {} more", rest)?;
+        }
+
+        Ok(())
+    }
+
+    unsafe fn debug_json(this: Ref<Self>, f: &mut fmt::Formatter) -> fmt::Result {
+        let max_depth = report::max_depth().unwrap_or(usize::MAX);
+        let mut chain = unsafe { Self::chain(this) };
+
+        f.write_str("{\"message\":")?;
+        if let Some(error) = chain.next() {
+            write!(JsonString(f), "{}", error)?;
+        }
+
+        f.write_str(",\"causes\":[")?;
+        for (n, cause) in chain.take(max_depth).enumerate() {
+            if n > 0 {
+                f.write_char(',')?;
+            }
+            write!(JsonString(f), "{}", cause)?;
+        }
+        f.write_char(']')?;
+
+        if let Some(code) = unsafe { Self::code(this) } {
+            f.write_str(",\"code\":")?;
+            write!(JsonString(f), "{}", code)?;
+        }
+
+        f.write_str(",\"severity\":")?;
+        write!(JsonString(f), "{}", unsafe { Self::severity(this) })?;
+
+        f.write_str(",\"sections\":[")?;
+        let sections = unsafe { Self::layers(this) }
+            .flat_map(|layer| unsafe { Self::attachments(layer) }.sections());
+        for (n, section) in sections.enumerate() {
+            if n > 0 {
+                f.write_char(',')?;
+            }
+            f.write_str("{\"kind\":")?;
+            write!(JsonString(f), "{}", section.kind.label())?;
+            f.write_str(",\"message\":")?;
+            write!(JsonString(f), "{}", section.message)?;
+            f.write_char('}')?;
+        }
+        f.write_char(']')?;
+
+        #[cfg(feature = "std")]
+        {
+            if unsafe { Self::sampled(this) } {
+                f.write_str(",\"sampled\":true")?;
+            }
+
+            if let Some(backtrace) = unsafe { Self::captured_backtrace(this) } {
+                f.write_str(",\"backtrace\":")?;
+                write!(JsonString(f), "{}", backtrace)?;
+
+                f.write_str(",\"frames\":[")?;
+                let frames = Frames::new(unsafe { Self::backtrace(this) });
+                for (n, frame) in frames.iter().enumerate() {
+                    if n > 0 {
+                        f.write_char(',')?;
+                    }
+                    f.write_str("{\"function\":")?;
+                    write!(JsonString(f), "{}", frame.function())?;
+                    if let (Some(file), Some(line), Some(column)) =
+                        (frame.file(), frame.line(), frame.column())
+                    {
+                        f.write_str(",\"file\":")?;
+                        write!(JsonString(f), "{}", file)?;
+                        write!(f, ",\"line\":{},\"column\":{}", line, column)?;
+                    }
+                    f.write_char('}')?;
+                }
+                f.write_char(']')?;
+            }
+        }
+
+        if let Some(trace) = unsafe { Self::trace(this) } {
+            f.write_str(",\"trace\":")?;
+            write!(JsonString(f), "{}", trace)?;
+        }
+
+        f.write_char('}')
+    }
+
+    // The backtrace if one was captured, rendered by render_backtrace.
+    #[cfg(feature = "std")]
+    unsafe fn captured_backtrace(this: Ref<Self>) -> Option<String> {
+        use std::backtrace::BacktraceStatus;
+
+        let backtrace = unsafe { Self::backtrace(this) };
+        if let BacktraceStatus::Captured = backtrace.status() {
+            Some(render_backtrace(backtrace))
+        } else {
+            None
+        }
+    }
+}
+
+// The backtrace without any "stack backtrace:" header and without trailing
+// whitespace.
+#[cfg(feature = "std")]
+fn render_backtrace(backtrace: &Backtrace) -> String {
+    use alloc::string::ToString;
+
+    let mut backtrace = backtrace.to_string();
+    // The "stack backtrace:" prefix was removed from std's rendering in
+    // https://github.com/rust-lang/backtrace-rs/pull/286 but handle it in
+    // case it's there.
+    if backtrace.starts_with("stack backtrace:") {
+        backtrace.replace_range(
+            ..backtrace.find('\n').map_or(backtrace.len(), |i| i + 1),
+            "",
+        );
+    }
+    backtrace.truncate(backtrace.trim_end().len());
+    backtrace
+}
+
+#[cfg(feature = "std")]
+fn write_backtrace(f: &mut fmt::Formatter, backtrace: &str, color: bool) -> fmt::Result {
+    use crate::backtrace::{is_hidden_frame, FrameChunks};
+
+    let hidden = report::hidden_frames();
+    let snippets = report::snippets();
+
+    fn write_hidden(f: &mut fmt::Formatter, first: bool, count: usize) -> fmt::Result {
+        if !first {
+            f.write_char('\n')?;
+        }
+        let s = if count == 1 { "" } else { "s" };
+        write!(f, "      ... {} frame{} hidden ...", count, s)
+    }
+
+    // Contents of the source files read so far, each read at most once.
+    let mut sources: Vec<(&str, Option<String>)> = Vec::new();
+    let mut first = true;
+    let mut hidden_count = 0;
+    for frame in FrameChunks::new(backtrace) {
+        if is_hidden_frame(frame.function, hidden) {
+            hidden_count += 1;
+            continue;
+        }
+        if hidden_count > 0 {
+            write_hidden(f, first, hidden_count)?;
+            first = false;
+            hidden_count = 0;
+        }
+        if !first {
+            f.write_char('\n')?;
+        }
+        f.write_str(frame.text)?;
+        if snippets {
+            if let Some((file, line, _column)) = frame.location() {
+                if !is_toolchain_source(file) {
+                    let i = match sources.iter().position(|&(path, _)| path == file) {
+                        Some(i) => i,
+                        None => {
+                            sources.push((file, std::fs::read_to_string(file).ok()));
+                            sources.len() - 1
+                        }
+                    };
+                    if let Some(source) = &sources[i].1 {
+                        write_snippet(f, source, line, color)?;
+                    }
+                }
+            }
+        }
+        first = false;
+    }
+    if hidden_count > 0 {
+        write_hidden(f, first, hidden_count)?;
+    }
+    Ok(())
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const CYAN: &str = "\x1b[1;36m";
+
+#[cfg(feature = "std")]
+fn write_sampled(f: &mut fmt::Formatter, color: bool) -> fmt::Result {
+    let heading = Styled(color, BOLD, "Stack backtrace:");
+    write!(f, "\n\n{} not captured (sampled)", heading)
+}
+
+// Number of lines of source code to show on either side of a frame's line.
+#[cfg(feature = "std")]
+const SNIPPET_CONTEXT: usize = 2;
+
+// Whether a source file belongs to the Rust toolchain or to a dependency
+// downloaded by Cargo rather than to the user's own code, judging by where it
+// is installed.
+#[cfg(feature = "std")]
+fn is_toolchain_source(file: &str) -> bool {
+    let file = file.replace('\\', "/");
+    file.starts_with("/rustc/")
+        || file.contains("/rustlib/src/")
+        || file.contains("/.rustup/")
+        || file.contains("/.cargo/registry/")
+        || file.contains("/.cargo/git/")
+}
+
+// Writes the lines of the given source code surrounding the given line, with
+// the line itself marked.
+#[cfg(feature = "std")]
+fn write_snippet(f: &mut fmt::Formatter, source: &str, line: u32, color: bool) -> fmt::Result {
+    let line = line as usize;
+    let first = line.saturating_sub(SNIPPET_CONTEXT).max(1);
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (i + 1, text))
+        .skip(first - 1)
+        .take(line + SNIPPET_CONTEXT + 1 - first);
+    let width = match lines.clone().last() {
+        Some((last, _)) if last >= line => format!("{}", last).len(),
+        _ => return Ok(()),
+    };
+    for (number, text) in lines {
+        if number == line {
+            let text = format!(">{:>width$} | {}", number, text, width = width);
+            write!(f, "\n             {}", Styled(color, BOLD, text))?;
+        } else {
+            write!(
+                f,
+                "\n              {:>width$} | {}",
+                number,
+                text,
+                width = width
+            )?;
+        }
+    }
+    Ok(())
+}
+
+struct Styled<T>(bool, &'static str, T);
+
+impl<T> Display for Styled<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Styled(color, style, text) = self;
+        if *color {
+            write!(f, "{}{}\x1b[0m", style, text)
+        } else {
+            Display::fmt(text, f)
+        }
+    }
+}
+
+// Replaces line breaks with spaces.
+struct OneLine<'a, D> {
+    inner: &'a mut D,
+}
+
+impl<T> Write for OneLine<'_, T>
+where
+    T: Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.inner.write_char(' ')?;
+            }
+            self.inner.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+// Writes the quoted and escaped JSON representation of everything written
+// into it. Each write_fmt produces one complete JSON string.
+struct JsonString<'a, D>(&'a mut D);
+
+impl<T> Write for JsonString<'_, T>
+where
+    T: Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.chars() {
+            match ch {
+                '"' => self.0.write_str("\\\"")?,
+                '\\' => self.0.write_str("\\\\")?,
+                '\n' => self.0.write_str("\\n")?,
+                '\r' => self.0.write_str("\\r")?,
+                '\t' => self.0.write_str("\\t")?,
+                ch if ch.is_control() => write!(self.0, "\\u{:04x}", ch as u32)?,
+                ch => self.0.write_char(ch)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> fmt::Result {
+        self.0.write_char('"')?;
+        fmt::write(self, args)?;
+        self.0.write_char('"')
+    }
 }
 
 struct Indented<'a, D> {
@@ -100,6 +474,27 @@ where
     }
 }
 
+struct HangingIndent<'a, D> {
+    inner: &'a mut D,
+    width: usize,
+}
+
+impl<T> Write for HangingIndent<'_, T>
+where
+    T: Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.inner.write_char('\n')?;
+                write!(self.inner, "{:1$}", "", self.width)?;
+            }
+            self.inner.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
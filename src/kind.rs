@@ -32,10 +32,10 @@
 // Since specialization is not stable yet, instead we rely on autoref behavior
 // of method resolution to perform tagged dispatch. Here we have two traits
 // AdhocKind and TraitKind that both have an anyhow_kind() method. AdhocKind is
-// implemented whether or not the caller's type has a std error impl, while
-// TraitKind is implemented only when a std error impl does exist. The ambiguity
-// is resolved by AdhocKind requiring an extra autoref so that it has lower
-// precedence.
+// implemented for every type, and requires Display and Debug only once its
+// new() is called, while TraitKind is implemented only when a std error impl
+// does exist. The ambiguity is resolved by AdhocKind requiring an extra
+// autoref so that it has lower precedence.
 //
 // The anyhow! macro will set up the call in this form:
 //
@@ -62,15 +62,32 @@ pub trait AdhocKind: Sized {
     }
 }
 
-impl<T> AdhocKind for &T where T: ?Sized + Display + Debug + Send + Sync + 'static {}
+impl<T> AdhocKind for &T where T: ?Sized {}
+
+// Required by Adhoc::new rather than by the AdhocKind impl, so that a value
+// that is neither an error nor a message is reported as an unsatisfied bound,
+// which can explain itself, instead of as a method that cannot be called.
+#[doc(hidden)]
+#[cfg_attr(
+    not(anyhow_no_diagnostic_namespace),
+    diagnostic::on_unimplemented(
+        message = "`{Self}` cannot be used as an `anyhow!` message",
+        label = "requires `Display + Debug + Send + Sync + 'static`",
+        note = "`anyhow!` takes a format string, a value implementing `Display`, or an error type implementing `std::error::Error`",
+    )
+)]
+pub trait AdhocMessage: Display + Debug + Send + Sync + 'static {}
+
+impl<M> AdhocMessage for M where M: Display + Debug + Send + Sync + 'static {}
 
 impl Adhoc {
     #[cold]
+    #[track_caller]
     pub fn new<M>(self, message: M) -> Error
     where
-        M: Display + Debug + Send + Sync + 'static,
+        M: AdhocMessage,
     {
-        Error::construct_from_adhoc(message, backtrace!())
+        Error::construct_from_adhoc(message, backtrace!(M))
     }
 }
 
@@ -88,6 +105,7 @@ impl<E> TraitKind for E where E: Into<Error> {}
 
 impl Trait {
     #[cold]
+    #[track_caller]
     pub fn new<E>(self, error: E) -> Error
     where
         E: Into<Error>,
@@ -114,6 +132,7 @@ impl BoxedKind for Box<dyn StdError + Send + Sync> {}
 #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
 impl Boxed {
     #[cold]
+    #[track_caller]
     pub fn new(self, error: Box<dyn StdError + Send + Sync>) -> Error {
         let backtrace = backtrace_if_absent!(&*error);
         Error::construct_from_boxed(error, backtrace)
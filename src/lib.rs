@@ -139,6 +139,9 @@
 //!   - If you want only panics to have backtraces, set `RUST_BACKTRACE=1` and
 //!     `RUST_LIB_BACKTRACE=0`.
 //!
+//!   Which errors capture a backtrace can also be controlled programmatically
+//!   through [`anyhow::backtrace::set_policy`][backtrace::set_policy].
+//!
 //!   [`std::backtrace`]: std::backtrace#environment-variables
 //!
 //! - Anyhow works with any error type that has an impl of `std::error::Error`,
@@ -205,6 +208,39 @@
 //! `.map_err(Error::msg)` when working with a non-Anyhow error type inside a
 //! function that returns Anyhow's error type, as the trait that `?`-based error
 //! conversions are defined by is only available in std in those old versions.
+//!
+//! Targets that want std for `std::error::Error` but not the size and runtime
+//! cost of `std::backtrace` can enable the "no-backtrace" feature, which
+//! removes backtrace capture and storage from `anyhow::Error` entirely.
+//!
+//! ```toml
+//! [dependencies]
+//! anyhow = { version = "1.0", features = ["no-backtrace"] }
+//! ```
+//!
+//! Binaries that attach context to many different error types can enable the
+//! "min-code-size" feature. Then `.context(...)` on a non-anyhow error wraps it
+//! in an `anyhow::Error` first, so that each context type needs one vtable
+//! instead of one per error type it is attached to, at the cost of one more
+//! allocation. Messages and context that are `&'static str` or `String` also
+//! share a single vtable between the two.
+//!
+//! ```toml
+//! [dependencies]
+//! anyhow = { version = "1.0", features = ["min-code-size"] }
+//! ```
+//!
+//! The "ensure-proc-macro" feature parses the condition of `ensure!` with a
+//! proc macro instead of a macro_rules parser. This compiles faster in crates
+//! with many `ensure!` calls, shows the operands of a comparison however
+//! complex the rest of the expression is, and points compile errors at the
+//! offending tokens. It requires Rust 1.71, the oldest compiler supported by
+//! syn.
+//!
+//! ```toml
+//! [dependencies]
+//! anyhow = { version = "1.0", features = ["ensure-proc-macro"] }
+//! ```
 
 #![doc(html_root_url = "https://docs.rs/anyhow/1.0.104")]
 #![cfg_attr(error_generic_member_access, feature(error_generic_member_access))]
@@ -248,18 +284,28 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+mod attachment;
 #[macro_use]
-mod backtrace;
+pub mod backtrace;
 mod chain;
+mod code;
 mod context;
-mod ensure;
+mod diff;
+// Process-wide settings for the messages of ensure!, used by tests and kept
+// out of the public API.
+#[doc(hidden)]
+pub mod ensure;
 mod error;
+#[cfg(feature = "std")]
+mod exit;
 mod fmt;
 mod kind;
 mod macros;
 #[cfg(error_generic_member_access)]
 mod nightly;
 mod ptr;
+pub mod report;
+mod severity;
 mod wrapper;
 
 use crate::error::ErrorImpl;
@@ -282,6 +328,11 @@ trait StdError: Debug + Display {
     }
 }
 
+pub use crate::code::Catalog;
+#[cfg(feature = "std")]
+pub use crate::exit::{main, ExitResult};
+pub use crate::severity::Severity;
+
 #[doc(no_inline)]
 pub use anyhow as format_err;
 
@@ -422,6 +473,9 @@ pub struct Chain<'a> {
 /// for `fn main`; if you do, failures will be printed along with any
 /// [context][Context] and a backtrace if one was captured.
 ///
+/// Returning `Result` from `fn main` exits with code 1 on failure. Use
+/// [`ExitResult`] instead to pick a different exit code based on the error.
+///
 /// `anyhow::Result` may be used with one *or* two type parameters.
 ///
 /// ```rust
@@ -625,6 +679,29 @@ pub trait Context<T, E>: context::private::Sealed {
     where
         C: Display + Send + Sync + 'static,
         F: FnOnce() -> C;
+
+    /// Attach a [help section][Error::help] to the error value, evaluated
+    /// lazily only once an error does occur.
+    ///
+    /// On an `Option`, the error in place of `None` is "called
+    /// `Option::with_help()` on a `None` value", and the help section is
+    /// attached to it.
+    ///
+    /// ```
+    /// use anyhow::{Context, Result};
+    /// use std::fs;
+    ///
+    /// fn remove_output(path: &str) -> Result<()> {
+    ///     fs::remove_file(path)
+    ///         .with_context(|| format!("failed to remove {}", path))
+    ///         .with_help(|| "check that the output directory is writable")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    fn with_help<H, F>(self, f: F) -> Result<T, Error>
+    where
+        H: Display + Send + Sync + 'static,
+        F: FnOnce() -> H;
 }
 
 /// Equivalent to `Ok::<_, anyhow::Error>(value)`.
@@ -660,13 +737,23 @@ pub mod __private {
     use core::fmt::Arguments;
 
     #[doc(hidden)]
-    pub use crate::ensure::{BothDebug, NotBothDebug};
+    pub use crate::ensure::{
+        approx_eq, ensure_approx_failed, ensure_match_failed, ensure_ne_failed, note_value,
+        render_values, to_f64, BothDebug, EqDebug, EqText, NotBothDebug, ValueDebug, ValueNotDebug,
+        Values,
+    };
     #[doc(hidden)]
     pub use alloc::format;
     #[doc(hidden)]
+    pub use core::option::Option::{None, Some};
+    #[doc(hidden)]
     pub use core::result::Result::Err;
     #[doc(hidden)]
-    pub use core::{concat, format_args, stringify};
+    pub use core::{compile_error, concat, format_args, stringify};
+
+    #[cfg(feature = "ensure-proc-macro")]
+    #[doc(hidden)]
+    pub use anyhow_impl::ensure;
 
     #[doc(hidden)]
     pub mod kind {
@@ -681,6 +768,7 @@ pub mod __private {
     #[doc(hidden)]
     #[inline]
     #[cold]
+    #[track_caller]
     pub fn format_err(args: Arguments) -> Error {
         if let Some(message) = args.as_str() {
             // anyhow!("literal"), can downcast to &'static str
@@ -691,6 +779,27 @@ pub mod __private {
         }
     }
 
+    #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
+    #[doc(hidden)]
+    #[inline]
+    #[cold]
+    #[track_caller]
+    pub fn format_err_with_source<E>(args: Arguments, source: E) -> Error
+    where
+        E: crate::StdError + Send + Sync + 'static,
+    {
+        if let Some(message) = args.as_str() {
+            Error::msg_with_source(message, source)
+        } else {
+            Error::msg_with_source(fmt::format(args), source)
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn vtable_addrs(error: &Error) -> alloc::vec::Vec<usize> {
+        crate::error::vtable_addrs(error)
+    }
+
     #[doc(hidden)]
     #[inline]
     #[cold]
@@ -699,6 +808,17 @@ pub mod __private {
         error
     }
 
+    // The `Err` of bail_into! and ensure_into!, converting the error the same
+    // way the `?` operator would.
+    #[doc(hidden)]
+    #[inline]
+    pub fn err_into<T, E>(error: Error) -> Result<T, E>
+    where
+        E: From<Error>,
+    {
+        Err(E::from(error))
+    }
+
     #[doc(hidden)]
     #[inline]
     pub fn not(cond: impl Bool) -> bool {
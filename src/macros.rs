@@ -6,6 +6,11 @@
 /// The surrounding function's or closure's return value is required to be
 /// <code>Result&lt;_, [anyhow::Error][crate::Error]&gt;</code>.
 ///
+/// Like `anyhow!`, it accepts optional leading `code = ...` and
+/// `severity = ...` arguments to attach an [error code][crate::Error::with_code]
+/// or a [severity][crate::Error::with_severity], and a `source = ...` argument
+/// to give the message an underlying [source error][crate::Error::msg_with_source].
+///
 /// [anyhow!]: crate::anyhow
 ///
 /// # Example
@@ -56,6 +61,15 @@
 #[macro_export]
 #[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
 macro_rules! bail {
+    (code = $code:expr, $($arg:tt)+) => {
+        return $crate::__private::Err($crate::__anyhow!(code = $code, $($arg)+))
+    };
+    (severity = $severity:expr, $($arg:tt)+) => {
+        return $crate::__private::Err($crate::__anyhow!(severity = $severity, $($arg)+))
+    };
+    (source = $source:expr, $($arg:tt)+) => {
+        return $crate::__private::Err($crate::__anyhow!(source = $source, $($arg)+))
+    };
     ($msg:literal $(,)?) => {
         return $crate::__private::Err($crate::__anyhow!($msg))
     };
@@ -67,6 +81,54 @@ macro_rules! bail {
     };
 }
 
+/// Return early with an error, converted into the error type of the
+/// function.
+///
+/// This is [`bail!`] for functions and closures that return
+/// <code>Result&lt;_, E&gt;</code> where `E` implements
+/// <code>From&lt;[anyhow::Error][crate::Error]&gt;</code>, such as a library's
+/// own error type. It is equivalent to
+/// <code>return Err(E::from([anyhow!($args\...)][anyhow!]))</code>, the same
+/// conversion that the `?` operator makes.
+///
+/// [anyhow!]: crate::anyhow
+///
+/// # Example
+///
+/// ```
+/// use anyhow::bail_into;
+///
+/// #[derive(Debug)]
+/// enum ConfigError {
+///     Invalid(anyhow::Error),
+/// }
+///
+/// impl From<anyhow::Error> for ConfigError {
+///     fn from(error: anyhow::Error) -> Self {
+///         ConfigError::Invalid(error)
+///     }
+/// }
+///
+/// fn parse_port(value: &str) -> Result<u16, ConfigError> {
+///     if value.is_empty() {
+///         bail_into!("port must not be empty");
+///     }
+///     // ...
+///     # Ok(0)
+/// }
+/// #
+/// # fn main() {
+/// #     assert!(matches!(parse_port(""), Err(ConfigError::Invalid(_))));
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+macro_rules! bail_into {
+    ($($arg:tt)+) => {
+        return $crate::__private::err_into($crate::__anyhow!($($arg)+))
+    };
+}
+
 macro_rules! __ensure {
     ($ensure:item) => {
         /// Return early with an error if a condition is not satisfied.
@@ -83,6 +145,17 @@ macro_rules! __ensure {
         ///
         /// [anyhow!]: crate::anyhow
         ///
+        /// Without a message, the error describes the condition that failed. If
+        /// the condition is operands or comparisons joined by `&&`, or joined by
+        /// `||`, the error lists the values of the operands of the term that was
+        /// false, or of every term for `||`:
+        ///
+        /// ```text
+        /// Condition failed: `a.len() > limit && cfg.enabled`
+        ///   a.len() = 3
+        ///   limit = 5
+        /// ```
+        ///
         /// # Example
         ///
         /// ```
@@ -152,7 +225,7 @@ __ensure![
     }
 ];
 
-#[cfg(not(doc))]
+#[cfg(all(not(doc), not(feature = "ensure-proc-macro")))]
 __ensure![
     #[macro_export]
     #[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
@@ -161,7 +234,106 @@ __ensure![
             $crate::__parse_ensure!(
                 /* state */ 0
                 /* stack */ ()
-                /* bail */ ($($tt)*)
+                /* bail */ (Err $($tt)*)
+                /* fuel */ (~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~)
+                /* parse */ {()}
+                /* dup */ ($($tt)*)
+                /* rest */ $($tt)*
+            )
+        };
+    }
+];
+
+#[cfg(all(not(doc), feature = "ensure-proc-macro"))]
+__ensure![
+    #[macro_export]
+    #[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+    macro_rules! ensure {
+        ($($tt:tt)*) => {
+            $crate::__private::ensure!($crate Err { $($tt)* })
+        };
+    }
+];
+
+macro_rules! __ensure_into {
+    ($ensure_into:item) => {
+        /// Return early with an error if a condition is not satisfied,
+        /// converted into the error type of the function.
+        ///
+        /// This is [`ensure!`] for functions and closures that return
+        /// <code>Result&lt;_, E&gt;</code> where `E` implements
+        /// <code>From&lt;[anyhow::Error][crate::Error]&gt;</code>, in the same
+        /// way that [`bail_into!`] is for [`bail!`]. The error is the same one
+        /// that `ensure!` would return, passed through `E::from`.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use anyhow::ensure_into;
+        ///
+        /// #[derive(Debug)]
+        /// struct LibError(anyhow::Error);
+        ///
+        /// impl From<anyhow::Error> for LibError {
+        ///     fn from(error: anyhow::Error) -> Self {
+        ///         LibError(error)
+        ///     }
+        /// }
+        ///
+        /// fn reserve(len: usize, capacity: usize) -> Result<(), LibError> {
+        ///     ensure_into!(len <= capacity);
+        ///     // ...
+        ///     # Ok(())
+        /// }
+        /// #
+        /// # fn main() {
+        /// #     let error = reserve(5, 3).unwrap_err();
+        /// #     assert_eq!(error.0.to_string(), "Condition failed: `len <= capacity` (5 vs 3)");
+        /// # }
+        /// ```
+        $ensure_into
+    };
+}
+
+#[cfg(doc)]
+__ensure_into![
+    #[macro_export]
+    macro_rules! ensure_into {
+        ($cond:expr $(,)?) => {
+            if !$cond {
+                return $crate::__private::err_into($crate::Error::msg(
+                    $crate::__private::concat!("Condition failed: `", $crate::__private::stringify!($cond), "`")
+                ));
+            }
+        };
+        ($cond:expr, $msg:literal $(,)?) => {
+            if !$cond {
+                return $crate::__private::err_into($crate::__anyhow!($msg));
+            }
+        };
+        ($cond:expr, $err:expr $(,)?) => {
+            if !$cond {
+                return $crate::__private::err_into($crate::__anyhow!($err));
+            }
+        };
+        ($cond:expr, $fmt:expr, $($arg:tt)*) => {
+            if !$cond {
+                return $crate::__private::err_into($crate::__anyhow!($fmt, $($arg)*));
+            }
+        };
+    }
+];
+
+#[cfg(all(not(doc), not(feature = "ensure-proc-macro")))]
+__ensure_into![
+    #[macro_export]
+    #[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+    macro_rules! ensure_into {
+        ($($tt:tt)*) => {
+            $crate::__parse_ensure!(
+                /* state */ 0
+                /* stack */ ()
+                /* bail */ (err_into $($tt)*)
                 /* fuel */ (~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~ ~~~~~~~~~~)
                 /* parse */ {()}
                 /* dup */ ($($tt)*)
@@ -171,6 +343,439 @@ __ensure![
     }
 ];
 
+#[cfg(all(not(doc), feature = "ensure-proc-macro"))]
+__ensure_into![
+    #[macro_export]
+    #[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+    macro_rules! ensure_into {
+        ($($tt:tt)*) => {
+            $crate::__private::ensure!($crate err_into { $($tt)* })
+        };
+    }
+];
+
+/// Return early with an error if two values are not equal.
+///
+/// This is the `Result`-returning counterpart of `assert_eq!`. Both values
+/// must implement `Debug`. Without a message of its own, the error shows the
+/// two values, ``Condition failed: `left == right` (1 vs 2)``, or if they are
+/// multi-line or long, a unified diff of the lines of their pretty `{:#?}`
+/// representations. Strings are diffed line by line as they are.
+///
+/// A message of its own takes the place of the condition, and the values or
+/// the diff follow it in the same way: `unexpected output length (6 vs 3)`.
+/// An error value given instead of a message keeps its own `Display`, with
+/// the condition and the values attached as a [note][crate::Error::note].
+///
+/// ```text
+/// Condition failed: `config == expected`
+/// --- left
+/// +++ right
+/// @@ -1,4 +1,4 @@
+///  Config {
+/// -    name: "staging",
+/// +    name: "production",
+///      replicas: 3,
+///  }
+/// ```
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::{ensure_eq, Result};
+/// #
+/// fn check(output: &str) -> Result<()> {
+///     ensure_eq!(output, "ok\n");
+///     ensure_eq!(output.len(), 3, "unexpected output length");
+///     Ok(())
+/// }
+/// #
+/// # fn main() {
+/// #     check("ok\n").unwrap();
+/// #     check("error\n").unwrap_err();
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+macro_rules! ensure_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    #[allow(unused_imports)]
+                    use $crate::__private::{EqDebug, EqText};
+                    return $crate::__private::Err((left, right).__dispatch_ensure_eq(
+                        $crate::__private::concat!(
+                            "Condition failed: `",
+                            $crate::__private::stringify!($left),
+                            " == ",
+                            $crate::__private::stringify!($right),
+                            "`",
+                        ),
+                    ));
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $msg:literal $(,)?) => {
+        $crate::__ensure_eq!($left, $right, text($crate::__private::format_args!($msg)))
+    };
+    ($left:expr, $right:expr, $fmt:literal, $($arg:tt)*) => {
+        $crate::__ensure_eq!($left, $right, text($crate::__private::format_args!($fmt, $($arg)*)))
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::__ensure_eq!($left, $right, error(
+            $crate::__anyhow!($($arg)+),
+            $crate::__private::concat!(
+                "Condition failed: `",
+                $crate::__private::stringify!($left),
+                " == ",
+                $crate::__private::stringify!($right),
+                "`",
+            ),
+        ))
+    };
+}
+
+// ensure_eq! with a message of its own, followed by the values the same way
+// as they follow the condition without one.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ensure_eq {
+    ($left:expr, $right:expr, $render:ident($($msg:tt)*)) => {{
+        let values = match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    #[allow(unused_imports)]
+                    use $crate::__private::{EqDebug, EqText};
+                    $crate::__private::Some((left, right).__ensure_eq_values())
+                } else {
+                    $crate::__private::None
+                }
+            }
+        };
+        // The operands are no longer borrowed when the message is evaluated.
+        if let $crate::__private::Some(values) = values {
+            return $crate::__private::Err(values.$render($($msg)*));
+        }
+    }};
+}
+
+/// Return early with an error if two values are equal.
+///
+/// This is the `Result`-returning counterpart of `assert_ne!`. Both values
+/// must implement `Debug`. Without a message of its own, the error shows the
+/// two values, ``Condition failed: `left != right` (1 vs 1)``.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::{ensure_ne, Result};
+/// #
+/// fn rename(from: &str, to: &str) -> Result<()> {
+///     ensure_ne!(from, to, "cannot rename {} to itself", from);
+///     // ...
+///     # Ok(())
+/// }
+/// #
+/// # fn main() {
+/// #     rename("a", "b").unwrap();
+/// #     rename("a", "a").unwrap_err();
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+macro_rules! ensure_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left == *right {
+                    return $crate::__private::Err($crate::__private::ensure_ne_failed(
+                        $crate::__private::concat!(
+                            "Condition failed: `",
+                            $crate::__private::stringify!($left),
+                            " != ",
+                            $crate::__private::stringify!($right),
+                            "`",
+                        ),
+                        left,
+                        right,
+                    ));
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left == *right {
+                    return $crate::__private::Err($crate::__anyhow!($($arg)+));
+                }
+            }
+        }
+    };
+}
+
+/// Return early with an error if two floating point numbers are not
+/// approximately equal.
+///
+/// The tolerance is given as `abs = ...`, `rel = ...`, or both, in that order.
+/// The values pass if the absolute difference between them is at most `abs`,
+/// or at most `rel` times the larger of their magnitudes. Equal values always
+/// pass, an infinity only passes against the same infinity, and NaN never
+/// passes. The values may be of any `Copy` type that converts into `f64`, such
+/// as `f32`.
+///
+/// Without a message of its own, the error shows both values, their absolute
+/// and relative difference, and the tolerance that each one exceeded:
+///
+/// ```text
+/// Condition failed: `total ≈ expected`
+///      left: 1.0000001
+///     right: 1.0
+///  abs diff: 1.0000000005838672e-7 > 1e-9
+///  rel diff: 9.99999900583877e-8 > 1e-8
+/// ```
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::{ensure_approx, Result};
+/// #
+/// fn check(total: f64, expected: f64) -> Result<()> {
+///     ensure_approx!(total, expected, abs = 1e-9, rel = 1e-8);
+///     ensure_approx!(total, expected, rel = 1e-3, "total is off by more than 0.1%");
+///     Ok(())
+/// }
+/// #
+/// # fn main() {
+/// #     check(0.1 + 0.2, 0.3).unwrap();
+/// #     check(1.0000001, 1.0).unwrap_err();
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+macro_rules! ensure_approx {
+    ($left:expr, $right:expr, abs = $abs:expr, rel = $rel:expr $(,)?) => {
+        $crate::__ensure_approx!($left, $right, $crate::__private::Some($abs), $crate::__private::Some($rel), ())
+    };
+    ($left:expr, $right:expr, abs = $abs:expr, rel = $rel:expr, $($arg:tt)+) => {
+        $crate::__ensure_approx!($left, $right, $crate::__private::Some($abs), $crate::__private::Some($rel), ($($arg)+))
+    };
+    ($left:expr, $right:expr, abs = $abs:expr $(,)?) => {
+        $crate::__ensure_approx!($left, $right, $crate::__private::Some($abs), $crate::__private::None, ())
+    };
+    ($left:expr, $right:expr, abs = $abs:expr, $($arg:tt)+) => {
+        $crate::__ensure_approx!($left, $right, $crate::__private::Some($abs), $crate::__private::None, ($($arg)+))
+    };
+    ($left:expr, $right:expr, rel = $rel:expr $(,)?) => {
+        $crate::__ensure_approx!($left, $right, $crate::__private::None, $crate::__private::Some($rel), ())
+    };
+    ($left:expr, $right:expr, rel = $rel:expr, $($arg:tt)+) => {
+        $crate::__ensure_approx!($left, $right, $crate::__private::None, $crate::__private::Some($rel), ($($arg)+))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ensure_approx {
+    ($left:expr, $right:expr, $abs:expr, $rel:expr, ()) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let (lhs, rhs) = ($crate::__private::to_f64(left), $crate::__private::to_f64(right));
+                let (abs, rel) = ($abs, $rel);
+                if !$crate::__private::approx_eq(lhs, rhs, abs, rel) {
+                    return $crate::__private::Err($crate::__private::ensure_approx_failed(
+                        $crate::__private::concat!(
+                            "Condition failed: `",
+                            $crate::__private::stringify!($left),
+                            " \u{2248} ",
+                            $crate::__private::stringify!($right),
+                            "`",
+                        ),
+                        left,
+                        right,
+                        lhs,
+                        rhs,
+                        abs,
+                        rel,
+                    ));
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $abs:expr, $rel:expr, ($($arg:tt)+)) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let (lhs, rhs) = ($crate::__private::to_f64(left), $crate::__private::to_f64(right));
+                if !$crate::__private::approx_eq(lhs, rhs, $abs, $rel) {
+                    return $crate::__private::Err($crate::__anyhow!($($arg)+));
+                }
+            }
+        }
+    };
+}
+
+/// Return early with an error if a value does not match a pattern.
+///
+/// This is the `Result`-returning counterpart of
+/// <code>assert!(matches!($value, $pattern))</code>, and takes the same
+/// pattern syntax as `matches!`, including an optional `if` guard. The error
+/// includes the `Debug` representation of the value, if it has one: shown in
+/// the message, ``Condition failed: `matches!(state, State::Ready)`
+/// (Closed)``, if it is short, and otherwise as a note.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::{ensure_matches, Result};
+/// #
+/// # #[derive(Debug)]
+/// # enum Response {
+/// #     Ok(u16),
+/// # }
+/// #
+/// fn check(response: Response) -> Result<()> {
+///     ensure_matches!(response, Response::Ok(200..=299), "request was not successful");
+///     // ...
+///     # Ok(())
+/// }
+/// #
+/// # fn main() {
+/// #     check(Response::Ok(200)).unwrap();
+/// #     check(Response::Ok(404)).unwrap_err();
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+macro_rules! ensure_matches {
+    ($value:expr, $pattern:pat $(if $guard:expr)? $(,)?) => {
+        match $value {
+            $pattern $(if $guard)? => {}
+            ref value => {
+                #[allow(unused_imports)]
+                use $crate::__private::{ValueDebug, ValueNotDebug};
+                return $crate::__private::Err($crate::__private::ensure_match_failed(
+                    $crate::__private::concat!(
+                        "Condition failed: `matches!(",
+                        $crate::__private::stringify!($value),
+                        ", ",
+                        $crate::__private::stringify!($pattern $(if $guard)?),
+                        ")`",
+                    ),
+                    (value,).__ensure_value(),
+                ));
+            }
+        }
+    };
+    ($value:expr, $pattern:pat $(if $guard:expr)?, $($arg:tt)+) => {
+        match $value {
+            $pattern $(if $guard)? => {}
+            ref value => {
+                #[allow(unused_imports)]
+                use $crate::__private::{ValueDebug, ValueNotDebug};
+                return $crate::__private::Err($crate::__private::note_value(
+                    $crate::__anyhow!($($arg)+),
+                    (value,).__ensure_value(),
+                ));
+            }
+        }
+    };
+}
+
+/// Bind variables by destructuring a value, or return early with an error if
+/// it does not match the pattern.
+///
+/// `ensure_let!(Some(x) = opt)` is like `let Some(x) = opt else { ... };`,
+/// binding `x` in the enclosing scope, where the `else` returns an error that
+/// includes the `Debug` representation of the value that did not match, as
+/// [`ensure_matches!`] does. A variable or a field of one, as in
+/// `ensure_let!(Some(ref user) = self.user)`, is matched in place like with
+/// `let`, so the pattern can borrow from it.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::{ensure_let, Result};
+/// # use std::collections::HashMap;
+/// #
+/// fn port(config: &HashMap<String, String>) -> Result<u16> {
+///     ensure_let!(Some(port) = config.get("port"), "missing port");
+///     ensure_let!(Ok(port) = port.parse::<u16>(), "invalid port {:?}", port);
+///     Ok(port)
+/// }
+/// #
+/// # fn main() {
+/// #     let mut config = HashMap::new();
+/// #     port(&config).unwrap_err();
+/// #     config.insert("port".to_owned(), "80".to_owned());
+/// #     assert_eq!(port(&config).unwrap(), 80);
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
+macro_rules! ensure_let {
+    // A variable or a field of one is matched in place, so that the pattern
+    // can borrow from it or move out of it like a plain `let` would. Reading
+    // it again for the error has no side effects.
+    ($pattern:pat = $place:ident $(. $field:tt)* $(,)?) => {
+        let $pattern = $place $(. $field)* else {
+            #[allow(unused_imports)]
+            use $crate::__private::{ValueDebug, ValueNotDebug};
+            return $crate::__private::Err($crate::__private::ensure_match_failed(
+                $crate::__private::concat!(
+                    "Condition failed: `let ",
+                    $crate::__private::stringify!($pattern),
+                    " = ",
+                    $crate::__private::stringify!($place $(. $field)*),
+                    "`",
+                ),
+                (&$place $(. $field)*,).__ensure_value(),
+            ));
+        };
+    };
+    ($pattern:pat = $place:ident $(. $field:tt)*, $($arg:tt)+) => {
+        let $pattern = $place $(. $field)* else {
+            #[allow(unused_imports)]
+            use $crate::__private::{ValueDebug, ValueNotDebug};
+            return $crate::__private::Err($crate::__private::note_value(
+                $crate::__anyhow!($($arg)+),
+                (&$place $(. $field)*,).__ensure_value(),
+            ));
+        };
+    };
+    // Any other expression is a temporary, which is evaluated once and kept
+    // for the error.
+    ($pattern:pat = $value:expr $(,)?) => {
+        let value = $value;
+        let $pattern = value else {
+            #[allow(unused_imports)]
+            use $crate::__private::{ValueDebug, ValueNotDebug};
+            return $crate::__private::Err($crate::__private::ensure_match_failed(
+                $crate::__private::concat!(
+                    "Condition failed: `let ",
+                    $crate::__private::stringify!($pattern),
+                    " = ",
+                    $crate::__private::stringify!($value),
+                    "`",
+                ),
+                (&value,).__ensure_value(),
+            ));
+        };
+    };
+    ($pattern:pat = $value:expr, $($arg:tt)+) => {
+        let value = $value;
+        let $pattern = value else {
+            #[allow(unused_imports)]
+            use $crate::__private::{ValueDebug, ValueNotDebug};
+            return $crate::__private::Err($crate::__private::note_value(
+                $crate::__anyhow!($($arg)+),
+                (&value,).__ensure_value(),
+            ));
+        };
+    };
+}
+
 /// Construct an ad-hoc error from a string or existing non-`anyhow` error
 /// value.
 ///
@@ -183,6 +788,35 @@ __ensure![
 /// Error impl's `source` is preserved as the `source` of the resulting
 /// `anyhow::Error`.
 ///
+/// An error code and a severity can be attached by passing `code = ...` or
+/// `severity = ...` as leading arguments; see
+/// [`Error::with_code`][crate::Error::with_code] and
+/// [`Error::with_severity`][crate::Error::with_severity].
+///
+/// ```
+/// # use anyhow::anyhow;
+/// #
+/// # let path = "";
+/// let error = anyhow!(code = "E0007", "no such file: {}", path);
+/// assert_eq!(error.code(), Some("E0007"));
+/// ```
+///
+/// A format string preceded by `source = ...` produces an error with that
+/// message whose `source()` is the given error, the same as
+/// [`Error::msg_with_source`][crate::Error::msg_with_source]. The `source`
+/// argument comes after any `code` or `severity`.
+///
+/// ```
+/// # use anyhow::anyhow;
+/// # use std::io;
+/// #
+/// # let path = "config.toml";
+/// # let err = io::Error::new(io::ErrorKind::NotFound, "no such file");
+/// let error = anyhow!(source = err, "failed to read {}", path);
+/// assert_eq!(error.to_string(), "failed to read config.toml");
+/// assert!(error.downcast_ref::<io::Error>().is_some());
+/// ```
+///
 /// # Example
 ///
 /// ```
@@ -202,6 +836,15 @@ __ensure![
 #[macro_export]
 #[cfg_attr(not(anyhow_no_clippy_format_args), clippy::format_args)]
 macro_rules! anyhow {
+    (code = $code:expr, $($arg:tt)+) => {
+        $crate::__private::must_use($crate::__anyhow!($($arg)+).with_code($code))
+    };
+    (severity = $severity:expr, $($arg:tt)+) => {
+        $crate::__private::must_use($crate::__anyhow!($($arg)+).with_severity($severity))
+    };
+    (source = $source:expr, $($arg:tt)+) => {
+        $crate::__private::must_use($crate::__anyhow!(source = $source, $($arg)+))
+    };
     ($msg:literal $(,)?) => {
         $crate::__private::must_use({
             let error = $crate::__private::format_err($crate::__private::format_args!($msg));
@@ -228,6 +871,21 @@ macro_rules! anyhow {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __anyhow {
+    (code = $code:expr, $($arg:tt)+) => {
+        $crate::__anyhow!($($arg)+).with_code($code)
+    };
+    (severity = $severity:expr, $($arg:tt)+) => {
+        $crate::__anyhow!($($arg)+).with_severity($severity)
+    };
+    (source = $source:expr, code = $($rest:tt)*) => {
+        $crate::__private::compile_error!("`code = ...` must come before `source = ...`")
+    };
+    (source = $source:expr, severity = $($rest:tt)*) => {
+        $crate::__private::compile_error!("`severity = ...` must come before `source = ...`")
+    };
+    (source = $source:expr, $($arg:tt)+) => {
+        $crate::__private::format_err_with_source($crate::__private::format_args!($($arg)+), $source)
+    };
     ($msg:literal $(,)?) => ({
         let error = $crate::__private::format_err($crate::__private::format_args!($msg));
         error
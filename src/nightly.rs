@@ -6,6 +6,7 @@
 
 use core::error::{self, Error};
 use std::backtrace::Backtrace;
+use std::process::ExitCode;
 
 pub use core::error::Request;
 
@@ -42,6 +43,10 @@ pub fn request_ref_backtrace(err: &dyn Error) -> Option<&Backtrace> {
     request_ref::<Backtrace>(err)
 }
 
+pub fn request_value_exit_code(err: &dyn Error) -> Option<ExitCode> {
+    error::request_value::<ExitCode>(err)
+}
+
 fn request_ref<'a, T>(err: &'a (impl Error + ?Sized)) -> Option<&'a T>
 where
     T: 'static + ?Sized,
@@ -0,0 +1,302 @@
+//! Settings for the `Debug` representation of [`Error`][crate::Error].
+//!
+//! The report printed by `{:?}`, which is what you get when returning an error
+//! from `fn main`, can be configured without rebuilding the program through
+//! the following environment variables. Each one is read once, the first time
+//! an error is formatted, and can be overridden programmatically at any time
+//! by the corresponding setter in this module.
+//!
+//! - `ANYHOW_REPORT=full|oneline|json` &mdash; [`set_format`]
+//! - `ANYHOW_COLOR=auto|always|never` &mdash; [`set_color`]
+//! - `ANYHOW_MAX_DEPTH=n` &mdash; [`set_max_depth`]
+//! - `ANYHOW_BACKTRACES=first|all` &mdash; [`set_all_backtraces`]
+//! - `ANYHOW_SNIPPETS=0|1` &mdash; [`set_snippets`]
+//!
+//! In addition, backtraces in the `Full` report are shown without the frames
+//! of anyhow itself, the standard library and common async runtimes, unless
+//! `RUST_LIB_BACKTRACE=full` or `RUST_BACKTRACE=full` is set &mdash;
+//! [`set_hidden_frames`].
+//!
+//! The alternate forms `{:#}` and `{:#?}` are not affected by these settings.
+
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::sync::{PoisonError, RwLock};
+
+/// Overall layout of the report.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The error message, its list of causes, any attached sections, and the
+    /// backtrace if one was captured. This is the default.
+    Full,
+    /// The error message followed by each of its causes on a single line,
+    /// separated by colons, like `{:#}`.
+    Oneline,
+    /// A single-line JSON object with the fields `"message"`, `"causes"`,
+    /// `"code"` (if any), `"severity"`, `"sections"`, `"sampled"` (if the
+    /// backtrace was skipped by [`Policy::Sample`]), `"backtrace"` and
+    /// `"frames"` (if captured), and `"trace"` (if any). Each element of
+    /// `"frames"` has a `"function"` and, if known, a `"file"`, `"line"` and
+    /// `"column"`.
+    ///
+    /// [`Policy::Sample`]: crate::backtrace::Policy::Sample
+    Json,
+}
+
+/// Whether the `Full` report uses ANSI colors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Color {
+    /// Use colors if standard error is a terminal and the `NO_COLOR`
+    /// environment variable is not set.
+    Auto,
+    /// Always use colors.
+    Always,
+    /// Never use colors. This is the default, as reports are often formatted
+    /// into strings that end up in places other than a terminal.
+    Never,
+}
+
+const UNSET: u8 = 0;
+
+static FORMAT: AtomicU8 = AtomicU8::new(UNSET);
+static COLOR: AtomicU8 = AtomicU8::new(UNSET);
+static ALL_BACKTRACES: AtomicU8 = AtomicU8::new(UNSET);
+static SNIPPETS: AtomicU8 = AtomicU8::new(UNSET);
+// Stores UNSET, or 1 + the maximum depth with usize::MAX meaning unlimited.
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(UNSET as usize);
+
+/// Module path prefixes of the backtrace frames that are hidden by default.
+#[cfg(feature = "std")]
+pub const DEFAULT_HIDDEN_FRAMES: &[&str] = &[
+    "anyhow::",
+    "core::",
+    "alloc::",
+    "std::",
+    "test::",
+    "tokio::runtime::",
+    "futures_executor::",
+];
+
+#[cfg(feature = "std")]
+static HIDDEN_FRAMES: RwLock<Option<&[&str]>> = RwLock::new(None);
+
+/// Set the layout of the report, overriding `ANYHOW_REPORT`.
+pub fn set_format(format: Format) {
+    FORMAT.store(encode_format(format), Ordering::Relaxed);
+}
+
+/// Set whether to use colors, overriding `ANYHOW_COLOR`.
+pub fn set_color(color: Color) {
+    COLOR.store(encode_color(color), Ordering::Relaxed);
+}
+
+/// Set the maximum number of causes to print below the error message,
+/// overriding `ANYHOW_MAX_DEPTH`. `None` means unlimited.
+pub fn set_max_depth(max_depth: Option<usize>) {
+    MAX_DEPTH.store(encode_max_depth(max_depth), Ordering::Relaxed);
+}
+
+/// Set whether the `Full` report shows every distinct backtrace found in the
+/// chain of causes, each under the cause it belongs to, rather than only the
+/// backtrace of the error itself. This overrides `ANYHOW_BACKTRACES`.
+///
+/// See [`Error::backtraces`][crate::Error::backtraces] for which backtraces
+/// can be found.
+pub fn set_all_backtraces(all: bool) {
+    ALL_BACKTRACES.store(encode_bool(all), Ordering::Relaxed);
+}
+
+/// Set whether the `Full` report shows the surrounding lines of source code
+/// under each backtrace frame that is not hidden, overriding
+/// `ANYHOW_SNIPPETS`. This is off by default.
+///
+/// Meant for local development: source files are read from disk, at the
+/// location recorded in the backtrace, when the report is formatted. Frames
+/// whose file cannot be read are shown without a snippet, as are frames in
+/// the sources of the Rust toolchain or of dependencies downloaded by Cargo.
+pub fn set_snippets(snippets: bool) {
+    SNIPPETS.store(encode_bool(snippets), Ordering::Relaxed);
+}
+
+/// The layout of the report currently in effect.
+pub fn format() -> Format {
+    let format = cached(&FORMAT, || {
+        encode_format(match var("ANYHOW_REPORT").as_deref() {
+            Some("oneline") => Format::Oneline,
+            Some("json") => Format::Json,
+            _ => Format::Full,
+        })
+    });
+    match format {
+        2 => Format::Oneline,
+        3 => Format::Json,
+        _ => Format::Full,
+    }
+}
+
+/// The color setting currently in effect.
+pub fn color() -> Color {
+    let color = cached(&COLOR, || {
+        encode_color(match var("ANYHOW_COLOR").as_deref() {
+            Some("auto") => Color::Auto,
+            Some("always") => Color::Always,
+            _ => Color::Never,
+        })
+    });
+    match color {
+        1 => Color::Auto,
+        2 => Color::Always,
+        _ => Color::Never,
+    }
+}
+
+/// Whether every distinct backtrace in the chain of causes is shown.
+pub fn all_backtraces() -> bool {
+    let all = cached(&ALL_BACKTRACES, || {
+        encode_bool(var("ANYHOW_BACKTRACES").as_deref() == Some("all"))
+    });
+    all == 2
+}
+
+/// Whether source code snippets are shown under backtrace frames.
+pub fn snippets() -> bool {
+    let snippets = cached(&SNIPPETS, || {
+        encode_bool(var("ANYHOW_SNIPPETS").as_deref() == Some("1"))
+    });
+    snippets == 2
+}
+
+/// The maximum number of causes to print currently in effect.
+pub fn max_depth() -> Option<usize> {
+    let mut max_depth = MAX_DEPTH.load(Ordering::Relaxed);
+    if max_depth == UNSET as usize {
+        let parsed = var("ANYHOW_MAX_DEPTH").and_then(|var| var.parse().ok());
+        max_depth = match MAX_DEPTH.compare_exchange(
+            UNSET as usize,
+            encode_max_depth(parsed),
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => encode_max_depth(parsed),
+            Err(max_depth) => max_depth,
+        };
+    }
+    match max_depth {
+        usize::MAX => None,
+        max_depth => Some(max_depth - 1),
+    }
+}
+
+/// Set the module path prefixes of backtrace frames to leave out of the
+/// `Full` report, in place of [`DEFAULT_HIDDEN_FRAMES`]. This overrides
+/// `RUST_LIB_BACKTRACE=full` and `RUST_BACKTRACE=full`.
+///
+/// A frame is hidden if its function name, ignoring any leading `<` of a
+/// trait impl, starts with one of the prefixes. For the `call_once` shims of
+/// closures the path of the trait is used instead, so `"core::"` hides them.
+/// Frames that have no module path at all, such as the C runtime's `main`,
+/// are hidden too. Runs of consecutive hidden frames are replaced by a line
+/// saying how many frames were hidden. Pass an empty list to show every frame.
+///
+/// ```
+/// use anyhow::report;
+///
+/// report::set_hidden_frames(&["anyhow::", "core::", "std::", "my_app::logging::"]);
+/// ```
+#[cfg(feature = "std")]
+pub fn set_hidden_frames(prefixes: &'static [&'static str]) {
+    *HIDDEN_FRAMES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(prefixes);
+}
+
+/// The module path prefixes of hidden backtrace frames currently in effect.
+#[cfg(feature = "std")]
+pub fn hidden_frames() -> &'static [&'static str] {
+    if let Some(prefixes) = *HIDDEN_FRAMES.read().unwrap_or_else(PoisonError::into_inner) {
+        return prefixes;
+    }
+    let full = match var("RUST_LIB_BACKTRACE") {
+        Some(lib_backtrace) => lib_backtrace == "full",
+        None => var("RUST_BACKTRACE").as_deref() == Some("full"),
+    };
+    let prefixes = if full { &[] } else { DEFAULT_HIDDEN_FRAMES };
+    *HIDDEN_FRAMES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert(prefixes)
+}
+
+// Whether the Full report should be rendered with ANSI colors.
+pub(crate) fn use_color() -> bool {
+    match color() {
+        Color::Always => true,
+        Color::Never => false,
+        Color::Auto => auto_color(),
+    }
+}
+
+fn cached(setting: &AtomicU8, init: impl FnOnce() -> u8) -> u8 {
+    let value = setting.load(Ordering::Relaxed);
+    if value != UNSET {
+        return value;
+    }
+    let value = init();
+    match setting.compare_exchange(UNSET, value, Ordering::Relaxed, Ordering::Relaxed) {
+        Ok(_) => value,
+        Err(existing) => existing,
+    }
+}
+
+fn encode_format(format: Format) -> u8 {
+    match format {
+        Format::Full => 1,
+        Format::Oneline => 2,
+        Format::Json => 3,
+    }
+}
+
+fn encode_color(color: Color) -> u8 {
+    match color {
+        Color::Auto => 1,
+        Color::Always => 2,
+        Color::Never => 3,
+    }
+}
+
+fn encode_bool(value: bool) -> u8 {
+    if value {
+        2
+    } else {
+        1
+    }
+}
+
+fn encode_max_depth(max_depth: Option<usize>) -> usize {
+    match max_depth {
+        Some(max_depth) => max_depth.saturating_add(1),
+        None => usize::MAX,
+    }
+}
+
+#[cfg(feature = "std")]
+fn var(key: &str) -> Option<std::string::String> {
+    std::env::var(key).ok()
+}
+
+#[cfg(not(feature = "std"))]
+fn var(key: &str) -> Option<alloc::string::String> {
+    let _ = key;
+    None
+}
+
+#[cfg(all(feature = "std", not(anyhow_no_is_terminal)))]
+fn auto_color() -> bool {
+    use std::io::IsTerminal;
+    std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+}
+
+#[cfg(not(all(feature = "std", not(anyhow_no_is_terminal))))]
+fn auto_color() -> bool {
+    false
+}
@@ -0,0 +1,41 @@
+use core::fmt::{self, Display};
+
+/// How serious an error is.
+///
+/// Severities are ordered from least to most severe, so that the highest
+/// severity found in an error's context chain can be determined with `max`.
+/// Refer to [`Error::with_severity`] and [`Error::severity`].
+///
+/// [`Error::with_severity`]: crate::Error::with_severity
+/// [`Error::severity`]: crate::Error::severity
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Diagnostic information that is normally not shown to users.
+    Debug,
+    /// A recoverable problem; the operation could continue.
+    Warning,
+    /// The operation failed. This is the severity of errors that do not have
+    /// one attached.
+    #[default]
+    Error,
+    /// The program cannot reasonably continue.
+    Fatal,
+}
+
+impl Severity {
+    /// The lowercase name of the severity, as in `"warning"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Fatal => "fatal",
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
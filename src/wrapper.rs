@@ -3,6 +3,14 @@ use core::fmt::{self, Debug, Display};
 
 #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
 use alloc::boxed::Box;
+#[cfg(feature = "min-code-size")]
+use alloc::string::String;
+#[cfg(feature = "min-code-size")]
+use core::any::TypeId;
+#[cfg(feature = "min-code-size")]
+use core::mem::{self, ManuallyDrop};
+#[cfg(feature = "min-code-size")]
+use core::ptr::{self, NonNull};
 
 #[cfg(error_generic_member_access)]
 use crate::nightly::{self, Request};
@@ -53,6 +61,79 @@ where
 
 impl<M> StdError for DisplayError<M> where M: Display + 'static {}
 
+// A string literal or an owned string, used as message or context in place of
+// either so that both share one vtable.
+#[cfg(feature = "min-code-size")]
+#[repr(u8)]
+pub enum StrMessage {
+    Literal(&'static str) = 0,
+    Owned(String) = 1,
+}
+
+#[cfg(feature = "min-code-size")]
+impl StrMessage {
+    pub fn new<M>(message: M) -> Result<Self, M>
+    where
+        M: 'static,
+    {
+        let message = ManuallyDrop::new(message);
+        let message_ptr: *const M = &*message;
+        if TypeId::of::<M>() == TypeId::of::<&'static str>() {
+            Ok(StrMessage::Literal(unsafe {
+                ptr::read(message_ptr.cast::<&'static str>())
+            }))
+        } else if TypeId::of::<M>() == TypeId::of::<String>() {
+            Ok(StrMessage::Owned(unsafe {
+                ptr::read(message_ptr.cast::<String>())
+            }))
+        } else {
+            Err(ManuallyDrop::into_inner(message))
+        }
+    }
+
+    // Points to the &'static str or String inside `message` if that is the
+    // type identified by `target`. Does not form a reference to `message`, so
+    // that the result may be written through if `message` may.
+    //
+    // Safety: `message` must point to a valid StrMessage.
+    pub unsafe fn downcast(message: *const StrMessage, target: TypeId) -> Option<NonNull<()>> {
+        // With repr(u8), each variant is laid out as a repr(C) struct of the
+        // u8 tag followed by the field, which puts the field at an offset of
+        // its own alignment.
+        let tag = unsafe { *message.cast::<u8>() };
+        let offset = if tag == 0 && target == TypeId::of::<&'static str>() {
+            mem::align_of::<&'static str>()
+        } else if tag == 1 && target == TypeId::of::<String>() {
+            mem::align_of::<String>()
+        } else {
+            return None;
+        };
+        let field = unsafe { message.cast::<u8>().add(offset) };
+        Some(unsafe { NonNull::new_unchecked(field.cast_mut().cast::<()>()) })
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            StrMessage::Literal(message) => message,
+            StrMessage::Owned(message) => message,
+        }
+    }
+}
+
+#[cfg(feature = "min-code-size")]
+impl Debug for StrMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(feature = "min-code-size")]
+impl Display for StrMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
 #[cfg(any(feature = "std", not(anyhow_no_core_error)))]
 #[repr(transparent)]
 pub struct BoxedError(pub Box<dyn StdError + Send + Sync>);
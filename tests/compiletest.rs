@@ -1,7 +1,17 @@
 #[rustversion::attr(not(nightly), ignore = "requires nightly")]
 #[cfg_attr(miri, ignore = "incompatible with miri")]
+#[cfg(not(feature = "ensure-proc-macro"))]
 #[test]
 fn ui() {
     let t = trybuild::TestCases::new();
     t.compile_fail("tests/ui/*.rs");
 }
+
+#[rustversion::attr(not(nightly), ignore = "requires nightly")]
+#[cfg_attr(miri, ignore = "incompatible with miri")]
+#[cfg(feature = "ensure-proc-macro")]
+#[test]
+fn ui_ensure_proc_macro() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui-ensure-proc-macro/*.rs");
+}
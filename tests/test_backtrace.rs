@@ -13,3 +13,244 @@ fn test_backtrace() {
     let error = anyhow!("oh no!");
     let _ = error.backtrace();
 }
+
+// Serializes the tests that change process-wide report settings.
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+static REPORT_SETTINGS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[test]
+fn test_policy() {
+    use anyhow::anyhow;
+    use anyhow::backtrace::{self, Policy};
+    use std::backtrace::BacktraceStatus;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // Other tests in this binary set only thread policies, so changing the
+    // process-wide one here does not affect them, but put it back regardless.
+    struct RestorePolicy(Policy);
+
+    impl Drop for RestorePolicy {
+        fn drop(&mut self) {
+            backtrace::set_thread_policy(None);
+            backtrace::set_policy(self.0);
+        }
+    }
+
+    let _restore = RestorePolicy(backtrace::policy());
+
+    backtrace::set_thread_policy(Some(Policy::Always));
+    let error = anyhow!("oh no!");
+    assert_eq!(BacktraceStatus::Captured, error.backtrace().status());
+
+    backtrace::set_thread_policy(Some(Policy::Never));
+    let error = anyhow!("oh no!");
+    assert_eq!(BacktraceStatus::Disabled, error.backtrace().status());
+
+    static SAW_STRING: AtomicBool = AtomicBool::new(false);
+    backtrace::set_policy(Policy::Always);
+    backtrace::set_thread_policy(Some(Policy::Filter(|type_name| {
+        if type_name == "alloc::string::String" {
+            SAW_STRING.store(true, Ordering::Relaxed);
+        }
+        false
+    })));
+    let error = anyhow!("oh {}!", "no");
+    assert_eq!(BacktraceStatus::Disabled, error.backtrace().status());
+    assert!(SAW_STRING.load(Ordering::Relaxed));
+
+    backtrace::set_thread_policy(None);
+    assert!(matches!(backtrace::policy(), Policy::Always));
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[test]
+fn test_hidden_frames() {
+    use anyhow::anyhow;
+    use anyhow::backtrace::{self, Policy};
+    use anyhow::report;
+    use std::sync::PoisonError;
+
+    let _settings = REPORT_SETTINGS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    // Called through a function pointer, the closure runs behind a
+    // `<{closure#0} as FnOnce<()>>::call_once` shim.
+    let closure: fn() -> anyhow::Error = || anyhow!("oh no!");
+
+    backtrace::set_thread_policy(Some(Policy::Always));
+    let error = closure();
+    backtrace::set_thread_policy(None);
+
+    report::set_hidden_frames(report::DEFAULT_HIDDEN_FRAMES);
+    let filtered = format!("{:?}", error);
+    assert!(filtered.contains("frames hidden ..."));
+    assert!(!filtered.contains(": std::"));
+    assert!(!filtered.contains(": anyhow::"));
+    assert!(!filtered.contains(" as core::ops::function::FnOnce"));
+
+    report::set_hidden_frames(&[]);
+    let full = format!("{:?}", error);
+    assert!(!full.contains("frames hidden"));
+    assert!(full.contains(" as core::ops::function::FnOnce"));
+    assert!(full.len() > filtered.len());
+
+    report::set_hidden_frames(report::DEFAULT_HIDDEN_FRAMES);
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[test]
+fn test_backtraces() {
+    use anyhow::anyhow;
+    use anyhow::backtrace::{self, Policy};
+    use anyhow::report;
+    use std::sync::PoisonError;
+
+    let _settings = REPORT_SETTINGS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    backtrace::set_thread_policy(Some(Policy::Always));
+    let error = anyhow!("inner").context("outer");
+    backtrace::set_thread_policy(None);
+
+    let backtraces: Vec<_> = error.backtraces().collect();
+    assert_eq!(1, backtraces.len());
+    assert_eq!(1, backtraces[0].0);
+
+    report::set_all_backtraces(true);
+    let debug = format!("{:?}", error);
+    report::set_all_backtraces(false);
+    assert!(debug.contains("\n\nStack backtrace of cause:\n"));
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[test]
+fn test_nested_backtraces() {
+    use anyhow::anyhow;
+    use anyhow::backtrace::{self, Policy};
+    use std::error::Error as StdError;
+    use std::fmt::{self, Display};
+
+    #[derive(Debug)]
+    struct Outer(Box<dyn StdError + Send + Sync>);
+
+    impl Display for Outer {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("outer")
+        }
+    }
+
+    impl StdError for Outer {
+        fn source(&self) -> Option<&(dyn StdError + 'static)> {
+            Some(&*self.0)
+        }
+    }
+
+    backtrace::set_thread_policy(Some(Policy::Always));
+    let inner = anyhow!("inner").context("middle");
+    let error = anyhow::Error::new(Outer(inner.into()));
+    backtrace::set_thread_policy(None);
+
+    // The chain is outer, middle, inner, and the inner error's backtrace was
+    // captured for "inner" rather than for the context around it.
+    let depths: Vec<_> = error.backtraces().map(|(depth, _)| depth).collect();
+    assert_eq!(depths, [0, 2]);
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[test]
+fn test_snippets() {
+    use anyhow::anyhow;
+    use anyhow::backtrace::{self, Policy};
+    use anyhow::report;
+    use std::sync::PoisonError;
+
+    let _settings = REPORT_SETTINGS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    backtrace::set_thread_policy(Some(Policy::Always));
+    let error = anyhow!("snippet"); // this line is shown
+    backtrace::set_thread_policy(None);
+
+    report::set_snippets(true);
+    let debug = format!("{:?}", error);
+    report::set_snippets(false);
+    assert!(debug.contains(" | fn test_snippets() {\n"));
+    assert!(debug.contains(" |     let error = anyhow!(\"snippet\"); // this line is shown\n"));
+    assert!(!format!("{:?}", error).contains("// this line is shown"));
+
+    // Frames in the standard library get no snippet even when shown.
+    report::set_hidden_frames(&[]);
+    report::set_snippets(true);
+    let debug = format!("{:?}", error);
+    report::set_snippets(false);
+    report::set_hidden_frames(report::DEFAULT_HIDDEN_FRAMES);
+    let lines: Vec<&str> = debug.lines().collect();
+    for pair in lines.windows(2) {
+        if pair[0].contains(" at /rustc/") || pair[0].contains("/rustlib/src/") {
+            assert!(!pair[1].contains(" | "), "{}", debug);
+        }
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[test]
+fn test_frames() {
+    use anyhow::anyhow;
+    use anyhow::backtrace::{self, Frames, Policy};
+    use std::backtrace::Backtrace;
+
+    assert!(Frames::new(&Backtrace::disabled()).is_empty());
+
+    backtrace::set_thread_policy(Some(Policy::Always));
+    let error = anyhow!("oh no!");
+    backtrace::set_thread_policy(None);
+
+    let frames = error.frames();
+    let frame = frames
+        .iter()
+        .find(|frame| frame.function().ends_with("test_frames"))
+        .unwrap();
+    assert_eq!("test_backtrace::test_frames", frame.function());
+    assert!(frame.file().unwrap().ends_with("test_backtrace.rs"));
+    assert!(frame.line().unwrap() > 0);
+    assert!(frame.column().unwrap() > 0);
+    assert!(!frame.is_hidden());
+}
+
+#[cfg(all(feature = "std", not(feature = "no-backtrace")))]
+#[test]
+fn test_sample() {
+    use anyhow::anyhow;
+    use anyhow::backtrace::{self, Policy};
+    use std::backtrace::{Backtrace, BacktraceStatus};
+    use std::time::Duration;
+
+    fn origin(n: u32) -> anyhow::Error {
+        anyhow!("sampled {}", n)
+    }
+
+    backtrace::set_thread_policy(Some(Policy::Sample {
+        max: 2,
+        window: Duration::from_secs(3600),
+    }));
+    let errors: Vec<_> = (0..4).map(origin).collect();
+    let other = anyhow!("other origin");
+    backtrace::set_thread_policy(None);
+
+    let sampled = |error: &anyhow::Error| format!("{:?}", error).contains("not captured (sampled)");
+    if Backtrace::capture().status() == BacktraceStatus::Captured {
+        assert!(!errors[..2].iter().any(sampled));
+        assert!(errors[2..].iter().all(sampled));
+        assert_eq!(BacktraceStatus::Disabled, errors[3].backtrace().status());
+        assert!(!sampled(&other));
+    } else {
+        // Backtraces are disabled by the environment, so none were skipped
+        // because of sampling.
+        assert!(!errors.iter().any(sampled));
+        assert_eq!(BacktraceStatus::Disabled, errors[0].backtrace().status());
+    }
+}
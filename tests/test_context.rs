@@ -187,6 +187,30 @@ fn test_downcast_mut() {
     assert!(dropped.all());
 }
 
+#[test]
+fn test_downcast_str_context() {
+    let dropped = Flag::new();
+    let err = Error::new(DetectDrop::new(&dropped))
+        .context("literal")
+        .context(String::from("owned"));
+
+    assert_eq!(*err.downcast_ref::<&str>().unwrap(), "literal");
+    assert_eq!(err.downcast_ref::<String>().unwrap(), "owned");
+
+    let err = err.context("outer");
+    let literal = err.downcast::<&str>().unwrap();
+    assert_eq!(literal, "outer");
+    assert!(dropped.get());
+
+    let dropped = Flag::new();
+    let mut err = Error::new(DetectDrop::new(&dropped)).context("literal");
+    *err.downcast_mut::<&str>().unwrap() = "clobber";
+    assert_eq!(err.to_string(), "clobber");
+    let err = err.context(String::from("owned"));
+    assert_eq!(err.downcast::<&str>().unwrap(), "clobber");
+    assert!(dropped.get());
+}
+
 #[test]
 fn test_root_cause() {
     let (err, _) = make_chain();
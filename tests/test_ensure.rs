@@ -139,7 +139,89 @@ fn test_low_precedence_binary_operator() {
     let a = 15;
     let b = 3;
     let test = || Ok(ensure!(a <= b || a - b <= 10));
-    assert_err(test, "Condition failed: `a <= b || a - b <= 10`");
+    assert_err(
+        test,
+        "Condition failed: `a <= b || a - b <= 10`\n  a = 15\n  b = 3\n  a - b = 12",
+    );
+}
+
+#[test]
+fn test_operand_values() {
+    struct Config {
+        enabled: bool,
+    }
+
+    let a = [1, 2, 3];
+    let limit = 5;
+    let cfg = Config { enabled: true };
+    let test = || Ok(ensure!(a.len() > limit && cfg.enabled));
+    assert_err(
+        test,
+        "Condition failed: `a.len() > limit && cfg.enabled`\n  a.len() = 3\n  limit = 5",
+    );
+
+    // Evaluation stops at the first false operand, like it would for `&&`.
+    let cfg = Config { enabled: false };
+    let test = || Ok(ensure!(cfg.enabled && a.get(10).is_some()));
+    assert_err(
+        test,
+        "Condition failed: `cfg.enabled && a.get(10).is_some()`\n  cfg.enabled = false",
+    );
+
+    let x = 5;
+    let test = || Ok(ensure!(x == 1 || x == 2 || x > 10));
+    assert_err(
+        test,
+        "Condition failed: `x == 1 || x == 2 || x > 10`\n  x = 5",
+    );
+
+    // Operands that are not Debug are left out.
+    #[derive(PartialEq)]
+    struct NotDebug;
+    let test = || Ok(ensure!(NotDebug != NotDebug || x != 5));
+    assert_err(
+        test,
+        "Condition failed: `NotDebug != NotDebug || x != 5`\n  x = 5",
+    );
+
+    // With &&, only the term that is false is listed.
+    let test = || Ok(ensure!(x == 5 && x > limit));
+    assert_err(
+        test,
+        "Condition failed: `x == 5 && x > limit`\n  x = 5\n  limit = 5",
+    );
+
+    // The borrows of one term end before the next term runs.
+    fn consume(s: String) -> bool {
+        s.is_empty()
+    }
+    let test = || {
+        let s = String::from("s");
+        let t = String::from("s");
+        Ok(ensure!(s == t && consume(s)))
+    };
+    assert_err(
+        test,
+        "Condition failed: `s == t && consume(s)`\n  consume(s) = false",
+    );
+
+    fn push(v: &mut Vec<i32>) -> bool {
+        v.push(1);
+        false
+    }
+    let test = || {
+        let mut v = vec![0];
+        let w = vec![1];
+        Ok(ensure!(v == w || push(&mut v)))
+    };
+    assert_err(
+        test,
+        "Condition failed: `v == w || push(&mut v)`\n  v = [0]\n  w = [1]\n  push(&mut v) = false",
+    );
+
+    // A mix of && and || keeps the plain message.
+    let test = || Ok(ensure!(x == 1 && x == 2 || x > 10));
+    assert_err(test, "Condition failed: `x == 1 && x == 2 || x > 10`");
 }
 
 #[test]
@@ -0,0 +1,55 @@
+use anyhow::{ensure_approx, Result};
+
+fn check(total: f64, expected: f64) -> Result<()> {
+    ensure_approx!(total, expected, abs = 1e-9, rel = 1e-8);
+    Ok(())
+}
+
+#[test]
+fn test_approx() {
+    check(0.1 + 0.2, 0.3).unwrap();
+    check(1e12 + 1e-3, 1e12).unwrap();
+    check(f64::INFINITY, f64::INFINITY).unwrap();
+
+    assert_eq!(
+        check(1.0000001, 1.0).unwrap_err().to_string(),
+        "Condition failed: `total \u{2248} expected`\n     left: 1.0000001\n    right: 1.0\n abs diff: 1.0000000005838672e-7 > 1e-9\n rel diff: 9.99999900583877e-8 > 1e-8",
+    );
+
+    check(f64::NAN, f64::NAN).unwrap_err();
+    check(f64::INFINITY, f64::NEG_INFINITY).unwrap_err();
+    check(f64::INFINITY, f64::MAX).unwrap_err();
+}
+
+#[test]
+fn test_one_tolerance() {
+    let test = |x: f32| -> Result<()> {
+        ensure_approx!(x, 2.0f32, abs = 0.5);
+        Ok(())
+    };
+    test(2.25).unwrap();
+    assert_eq!(
+        test(3.0).unwrap_err().to_string(),
+        "Condition failed: `x \u{2248} 2.0f32`\n     left: 3.0\n    right: 2.0\n abs diff: 1.0 > 0.5",
+    );
+
+    let test = |x: f64| -> Result<()> {
+        ensure_approx!(x, 100.0, rel = 0.01);
+        Ok(())
+    };
+    test(100.5).unwrap();
+    assert_eq!(
+        test(98.0).unwrap_err().to_string(),
+        "Condition failed: `x \u{2248} 100.0`\n     left: 98.0\n    right: 100.0\n rel diff: 0.02 > 0.01",
+    );
+}
+
+#[test]
+fn test_message() {
+    let test = |x: f64| -> Result<()> {
+        ensure_approx!(x, 1.0, abs = 1e-6, rel = 1e-6, "x drifted to {}", x);
+        Ok(())
+    };
+    test(1.0).unwrap();
+    assert_eq!(test(1.5).unwrap_err().to_string(), "x drifted to 1.5");
+}
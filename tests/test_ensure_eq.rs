@@ -0,0 +1,163 @@
+use anyhow::{ensure_eq, ensure_ne, Result};
+use std::io;
+
+#[derive(Debug, PartialEq)]
+struct Config {
+    name: &'static str,
+    replicas: u32,
+    regions: Vec<&'static str>,
+}
+
+#[test]
+fn test_eq() {
+    let test = || -> Result<()> {
+        ensure_eq!(1 + 1, 2);
+        ensure_eq!(String::from("x"), "x");
+        Ok(())
+    };
+    test().unwrap();
+
+    let test = || -> Result<()> {
+        ensure_eq!(1 + 1, 3);
+        Ok(())
+    };
+    assert_eq!(
+        test().unwrap_err().to_string(),
+        "Condition failed: `1 + 1 == 3` (2 vs 3)",
+    );
+}
+
+#[test]
+fn test_eq_diff() {
+    let test = || -> Result<()> {
+        let config = Config {
+            name: "staging",
+            replicas: 3,
+            regions: vec!["us", "eu"],
+        };
+        let expected = Config {
+            name: "production",
+            replicas: 3,
+            regions: vec!["us", "eu"],
+        };
+        ensure_eq!(config, expected);
+        Ok(())
+    };
+    let expected = "\
+Condition failed: `config == expected`
+--- left
++++ right
+@@ -1,5 +1,5 @@
+ Config {
+-    name: \"staging\",
++    name: \"production\",
+     replicas: 3,
+     regions: [
+         \"us\",";
+    assert_eq!(test().unwrap_err().to_string(), expected);
+}
+
+#[test]
+fn test_eq_text() {
+    let test = || -> Result<()> {
+        let output = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        ensure_eq!(output, "1\n2\n3\n4\n5\n6\n7\nseven\n8\n9\n10\n11\n12\n");
+        Ok(())
+    };
+    let expected = "\
+Condition failed: `output == \"1\\n2\\n3\\n4\\n5\\n6\\n7\\nseven\\n8\\n9\\n10\\n11\\n12\\n\"`
+--- left
++++ right
+@@ -5,6 +5,7 @@
+ 5
+ 6
+ 7
++seven
+ 8
+ 9
+ 10";
+    assert_eq!(test().unwrap_err().to_string(), expected);
+}
+
+#[test]
+fn test_eq_large_text() {
+    // Too many differing lines to search for common ones, so all of the left
+    // is removed and all of the right added.
+    let left: String = (0..300).map(|i| format!("a{}\n", i)).collect();
+    let right: String = (0..300).map(|i| format!("b{}\n", i)).collect();
+    let test = || -> Result<()> {
+        ensure_eq!(left, right);
+        Ok(())
+    };
+    let message = test().unwrap_err().to_string();
+    let lines: Vec<&str> = message.lines().collect();
+    assert_eq!(lines[3], "@@ -1,300 +1,300 @@");
+    assert_eq!(lines[4], "-a0");
+    assert_eq!(lines[303], "-a299");
+    assert_eq!(lines[304], "+b0");
+    assert_eq!(lines.len(), 604);
+}
+
+#[test]
+fn test_eq_message() {
+    let test = || -> Result<()> {
+        ensure_eq!(1, 2, "checksum mismatch in {}", "header");
+        Ok(())
+    };
+    assert_eq!(
+        test().unwrap_err().to_string(),
+        "checksum mismatch in header (1 vs 2)",
+    );
+
+    let test = || -> Result<()> {
+        ensure_eq!("a\nb\n", "a\nc\n", "unexpected output");
+        Ok(())
+    };
+    let expected = "\
+unexpected output
+--- left
++++ right
+@@ -1,2 +1,2 @@
+ a
+-b
++c";
+    assert_eq!(test().unwrap_err().to_string(), expected);
+
+    let test = || -> Result<()> {
+        let status = 404;
+        ensure_eq!(
+            status,
+            200,
+            io::Error::new(io::ErrorKind::Other, "bad status")
+        );
+        Ok(())
+    };
+    let error = test().unwrap_err();
+    assert_eq!(error.to_string(), "bad status");
+    assert!(error.downcast_ref::<io::Error>().is_some());
+    assert!(format!("{:?}", error).contains("note: Condition failed: `status == 200` (404 vs 200)"));
+}
+
+#[test]
+fn test_ne() {
+    let test = || -> Result<()> {
+        ensure_ne!(1, 2);
+        Ok(())
+    };
+    test().unwrap();
+
+    let test = || -> Result<()> {
+        ensure_ne!(1, 1);
+        Ok(())
+    };
+    assert_eq!(
+        test().unwrap_err().to_string(),
+        "Condition failed: `1 != 1` (1 vs 1)",
+    );
+
+    let test = || -> Result<()> {
+        ensure_ne!("a", "a", "cannot rename {} to itself", "a");
+        Ok(())
+    };
+    assert_eq!(test().unwrap_err().to_string(), "cannot rename a to itself",);
+}
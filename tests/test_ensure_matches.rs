@@ -0,0 +1,110 @@
+use anyhow::{ensure_let, ensure_matches, Result};
+
+#[derive(Debug)]
+#[allow(dead_code)]
+enum State {
+    Ready,
+    Closed { reason: &'static str },
+}
+
+#[test]
+fn test_matches() {
+    let test = |state: State| -> Result<()> {
+        ensure_matches!(state, State::Ready);
+        Ok(())
+    };
+    test(State::Ready).unwrap();
+    let error = test(State::Closed { reason: "eof" }).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Condition failed: `matches!(state, State::Ready)`",
+    );
+    assert!(format!("{:?}", error)
+        .contains("note: value: Closed {\n          reason: \"eof\",\n      }",));
+
+    let test = |n: i32| -> Result<()> {
+        ensure_matches!(n, 1..=9 if n % 2 == 1);
+        Ok(())
+    };
+    test(3).unwrap();
+    assert_eq!(
+        test(4).unwrap_err().to_string(),
+        "Condition failed: `matches!(n, 1..=9 if n % 2 == 1)` (4)",
+    );
+
+    let test = |n: i32| -> Result<()> {
+        ensure_matches!(n, 0, "expected zero, got {}", n);
+        Ok(())
+    };
+    let error = test(4).unwrap_err();
+    assert_eq!(error.to_string(), "expected zero, got 4");
+    assert!(format!("{:?}", error).contains("note: value: 4"));
+}
+
+#[test]
+fn test_let() {
+    let test = |opt: Option<String>| -> Result<String> {
+        ensure_let!(Some(x) = opt);
+        Ok(x)
+    };
+    assert_eq!(test(Some("x".to_owned())).unwrap(), "x");
+    assert_eq!(
+        test(None).unwrap_err().to_string(),
+        "Condition failed: `let Some(x) = opt` (None)",
+    );
+
+    let test = |result: Result<u8, String>| -> Result<u8> {
+        ensure_let!(Ok(n) = result, "missing n");
+        Ok(n)
+    };
+    assert_eq!(test(Ok(1)).unwrap(), 1);
+    let error = test(Err("bad input".to_owned())).unwrap_err();
+    assert_eq!(error.to_string(), "missing n");
+    assert!(format!("{:?}", error).contains("note: value: Err(\n"));
+}
+
+#[test]
+fn test_let_place() {
+    struct Request {
+        user: Option<String>,
+    }
+
+    impl Request {
+        fn user(&self) -> Result<&str> {
+            ensure_let!(Some(ref user) = self.user, "missing user");
+            Ok(user)
+        }
+    }
+
+    let request = Request {
+        user: Some("root".to_owned()),
+    };
+    assert_eq!(request.user().unwrap(), "root");
+    let request = Request { user: None };
+    let error = request.user().unwrap_err();
+    assert_eq!(error.to_string(), "missing user");
+    assert!(format!("{:?}", error).contains("note: value: None"));
+
+    let test = |request: Request| -> Result<String> {
+        ensure_let!(Some(user) = request.user);
+        Ok(user)
+    };
+    assert_eq!(
+        test(Request { user: None }).unwrap_err().to_string(),
+        "Condition failed: `let Some(user) = request.user` (None)",
+    );
+}
+
+#[test]
+fn test_not_debug() {
+    struct NotDebug;
+
+    let test = |opt: Option<NotDebug>| -> Result<()> {
+        ensure_let!(Some(NotDebug) = opt);
+        Ok(())
+    };
+    assert_eq!(
+        test(None).unwrap_err().to_string(),
+        "Condition failed: `let Some(NotDebug) = opt`",
+    );
+}
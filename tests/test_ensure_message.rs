@@ -0,0 +1,106 @@
+use anyhow::ensure::CustomMessage;
+use anyhow::{ensure, Result};
+use std::fmt::{self, Display};
+use std::io;
+
+#[derive(Debug, PartialEq)]
+struct Status(u16);
+
+#[derive(Debug)]
+struct StatusError(Status);
+
+impl Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unexpected status {}", self.0 .0)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+fn check_text(checksum: u32) -> Result<()> {
+    ensure!(checksum == 42, "checksum mismatch");
+    Ok(())
+}
+
+fn check_format(checksum: u32, block: usize) -> Result<()> {
+    ensure!(checksum == 42, "checksum mismatch in block {}", block);
+    Ok(())
+}
+
+fn check_error(checksum: u32) -> Result<()> {
+    ensure!(
+        checksum == 42,
+        io::Error::new(io::ErrorKind::Other, "checksum mismatch")
+    );
+    Ok(())
+}
+
+// The error takes the operand by value.
+fn check_status(status: Status) -> Result<()> {
+    ensure!(status == Status(200), StatusError(status));
+    Ok(())
+}
+
+// Printed with the spacing of the source, the same whether or not the
+// "ensure-proc-macro" feature is enabled.
+#[rustfmt::skip]
+fn check_spacing(blocks: &[u8], total: i32) -> Result<()> {
+    ensure!(
+        blocks.iter().map(|b|*b as i32).sum::<i32>() == total,
+        "size mismatch in {} blocks",
+        blocks.len(),
+    );
+    ensure!(blocks[..] != [0u8,0], "empty blocks");
+    Ok(())
+}
+
+// The setting is global, so it is exercised in sequence by one test.
+#[test]
+fn test_custom_message() {
+    // Default: the message alone.
+    assert_eq!(anyhow::ensure::custom_message(), CustomMessage::Alone);
+    let error = check_text(31).unwrap_err();
+    assert_eq!(error.to_string(), "checksum mismatch");
+    assert!(error.downcast_ref::<&str>().is_some());
+    assert!(!format!("{:?}", error).contains("note:"));
+    let error = check_status(Status(404)).unwrap_err();
+    assert_eq!(error.to_string(), "unexpected status 404");
+    assert_eq!(error.downcast_ref::<StatusError>().unwrap().0, Status(404));
+
+    anyhow::ensure::set_custom_message(CustomMessage::Inline);
+    assert_eq!(
+        check_text(31).unwrap_err().to_string(),
+        "checksum mismatch (31 vs 42)",
+    );
+    assert_eq!(
+        check_format(31, 7).unwrap_err().to_string(),
+        "checksum mismatch in block 7 (31 vs 42)",
+    );
+    let error = check_error(31).unwrap_err();
+    assert_eq!(error.to_string(), "checksum mismatch");
+    assert!(error.downcast_ref::<io::Error>().is_some());
+    assert!(format!("{:?}", error).contains("note: Condition failed: `checksum == 42` (31 vs 42)"));
+    let error = check_status(Status(404)).unwrap_err();
+    assert!(format!("{:?}", error)
+        .contains("note: Condition failed: `status == Status(200)` (Status(404) vs Status(200))"));
+
+    anyhow::ensure::set_custom_message(CustomMessage::Note);
+    let error = check_format(31, 7).unwrap_err();
+    assert_eq!(error.to_string(), "checksum mismatch in block 7");
+    assert!(format!("{:?}", error).contains("note: Condition failed: `checksum == 42` (31 vs 42)"));
+
+    let error = check_spacing(&[1, 2], 4).unwrap_err();
+    assert!(format!("{:?}", error).contains(
+        "note: Condition failed: `blocks.iter().map(|b|*b as i32).sum::<i32>() == total` (3 vs 4)"
+    ));
+    let error = check_spacing(&[0, 0], 0).unwrap_err();
+    assert!(format!("{:?}", error).contains("note: Condition failed: `blocks[..] != [0u8,0]`"));
+
+    // Passing checks are unaffected.
+    check_text(42).unwrap();
+    check_error(42).unwrap();
+    check_status(Status(200)).unwrap();
+    check_spacing(&[1, 2], 3).unwrap();
+
+    anyhow::ensure::set_custom_message(CustomMessage::Alone);
+}
@@ -0,0 +1,61 @@
+#![allow(clippy::uninlined_format_args)]
+
+use anyhow::{ensure, Result};
+
+#[derive(Debug, PartialEq)]
+struct Config {
+    name: &'static str,
+}
+
+fn check_len(len: usize) -> Result<()> {
+    ensure!(len == 0);
+    Ok(())
+}
+
+fn check_config(config: Config) -> Result<()> {
+    ensure!(config == Config { name: "a c" });
+    Ok(())
+}
+
+// The settings are global, so they are exercised in sequence by one test.
+#[test]
+fn test_settings() {
+    let long = 1usize << 60;
+
+    // Default: operands over 40 bytes or with whitespace are left out.
+    anyhow::ensure::set_max_operand_len(10);
+    assert_eq!(
+        check_len(long).unwrap_err().to_string(),
+        "Condition failed: `len == 0`",
+    );
+    assert_eq!(
+        check_config(Config { name: "a b" })
+            .unwrap_err()
+            .to_string(),
+        "Condition failed: `config == Config { name: \"a c\" }`",
+    );
+
+    anyhow::ensure::set_truncate(true);
+    assert_eq!(
+        check_len(long).unwrap_err().to_string(),
+        "Condition failed: `len == 0` (1152921504\u{2026} (truncated) vs 0)",
+    );
+    assert_eq!(
+        check_len(7).unwrap_err().to_string(),
+        "Condition failed: `len == 0` (7 vs 0)",
+    );
+
+    anyhow::ensure::set_max_operand_len(100);
+    anyhow::ensure::set_multiline(true);
+    assert_eq!(
+        check_config(Config { name: "a b" }).unwrap_err().to_string(),
+        "Condition failed: `config == Config { name: \"a c\" }`\n  left: Config {\n    name: \"a b\",\n}\n right: Config {\n    name: \"a c\",\n}",
+    );
+
+    // Lines of their own are not held to the maximum length.
+    anyhow::ensure::set_max_operand_len(40);
+    assert_eq!(
+        check_config(Config { name: "a name longer than the forty byte maximum" }).unwrap_err().to_string(),
+        "Condition failed: `config == Config { name: \"a c\" }`\n  left: Config {\n    name: \"a name longer than the forty byte maximum\",\n}\n right: Config {\n    name: \"a c\",\n}",
+    );
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "std")]
+
+use anyhow::{anyhow, Context, ExitResult, Result, Severity};
+use std::io;
+use std::process::{ExitCode, Termination};
+
+fn fail() -> Result<()> {
+    Err(anyhow!("oh no!").with_exit_code(3)).context("outer")
+}
+
+#[test]
+fn test_success() {
+    assert_eq!(ExitCode::SUCCESS, anyhow::main(|| Ok(())).report());
+}
+
+#[test]
+fn test_default() {
+    let result = anyhow::main(|| Err(anyhow!("oh no!")));
+    assert_eq!(ExitCode::FAILURE, result.report());
+}
+
+#[test]
+fn test_attached() {
+    let err = fail().unwrap_err();
+    assert_eq!(Some(3), err.exit_code());
+    assert_eq!(ExitCode::from(3), ExitResult::from(err).report());
+}
+
+#[test]
+fn test_mapping() {
+    let mapping = |error: &anyhow::Error| match error.severity() {
+        Severity::Warning => Some(0),
+        _ => None,
+    };
+
+    let result = anyhow::main(|| Err(anyhow!(severity = Severity::Warning, "oh no!")));
+    assert_eq!(ExitCode::from(0), result.exit_code_with(mapping).report());
+
+    let result = anyhow::main(fail);
+    assert_eq!(ExitCode::from(3), result.exit_code_with(mapping).report());
+}
+
+#[test]
+fn test_source_chain() {
+    let not_found = || {
+        let io_error = io::Error::new(io::ErrorKind::NotFound, "oh no!");
+        Err::<(), _>(io_error).context("outer")
+    };
+
+    // Source errors only determine the exit code through the mapping
+    // function.
+    assert_eq!(ExitCode::FAILURE, anyhow::main(not_found).report());
+
+    let mapping = |error: &anyhow::Error| {
+        let io_error = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<io::Error>())?;
+        match io_error.kind() {
+            io::ErrorKind::NotFound => Some(66),
+            _ => None,
+        }
+    };
+    let result = anyhow::main(not_found).exit_code_with(mapping);
+    assert_eq!(ExitCode::from(66), result.report());
+}
@@ -91,3 +91,59 @@ fn test_altdebug() {
     assert_eq!(EXPECTED_ALTDEBUG_G, format!("{:#?}", g().unwrap_err()));
     assert_eq!(EXPECTED_ALTDEBUG_H, format!("{:#?}", h().unwrap_err()));
 }
+
+#[test]
+fn test_sections() {
+    let error = h()
+        .unwrap_err()
+        .note("first note")
+        .help("multi-line\nhelp")
+        .context("outer")
+        .suggestion("try again");
+
+    let expected = "\
+outer
+
+Caused by:
+    0: g failed
+    1: f failed
+    2: oh no!
+
+suggestion: try again
+note: first note
+help: multi-line
+      help\
+";
+
+    assert_eq!("outer", error.to_string());
+    assert_eq!(4, error.chain().count());
+    assert_eq!(expected, format!("{:?}", error));
+}
+
+#[test]
+fn test_with_help() {
+    let error = f().with_help(|| "check permissions").unwrap_err();
+    assert_eq!("oh no!\n\nhelp: check permissions", format!("{:?}", error));
+
+    let error = Err::<(), io::Error>(io::Error::other("oh no!"))
+        .with_help(|| "check permissions")
+        .unwrap_err();
+    assert!(error.is::<io::Error>());
+    assert_eq!("oh no!\n\nhelp: check permissions", format!("{:?}", error));
+
+    let error = None::<()>.with_help(|| "check permissions").unwrap_err();
+    assert_eq!(
+        "called `Option::with_help()` on a `None` value\n\nhelp: check permissions",
+        format!("{:?}", error),
+    );
+}
+
+#[test]
+fn test_code() {
+    let error = g().unwrap_err().with_code("E0042");
+    assert_eq!("f failed", error.to_string());
+    assert_eq!(
+        "[E0042] f failed\n\nCaused by:\n    oh no!",
+        format!("{:?}", error),
+    );
+}
@@ -0,0 +1,73 @@
+use anyhow::{bail_into, ensure_into};
+
+#[derive(Debug)]
+struct MyError(anyhow::Error);
+
+impl From<anyhow::Error> for MyError {
+    fn from(error: anyhow::Error) -> Self {
+        MyError(error)
+    }
+}
+
+#[test]
+fn test_bail_into() {
+    let test = || -> Result<(), MyError> {
+        bail_into!("oh no!");
+    };
+    assert_eq!(test().unwrap_err().0.to_string(), "oh no!");
+
+    let test = |n: i32| -> Result<(), MyError> {
+        bail_into!(code = "E0001", "bad value {}", n);
+    };
+    let error = test(1).unwrap_err().0;
+    assert_eq!(error.to_string(), "bad value 1");
+    assert_eq!(error.code(), Some("E0001"));
+
+    // anyhow::Error itself converts by the identity From impl.
+    let test = || -> anyhow::Result<()> {
+        bail_into!("oh no!");
+    };
+    assert_eq!(test().unwrap_err().to_string(), "oh no!");
+}
+
+#[test]
+fn test_ensure_into() {
+    let test = |a: usize, b: usize| -> Result<(), MyError> {
+        ensure_into!(a <= b);
+        Ok(())
+    };
+    test(1, 2).unwrap();
+    let error = test(5, 3).unwrap_err().0;
+    assert_eq!(error.to_string(), "Condition failed: `a <= b` (5 vs 3)");
+
+    let test = |a: usize, limit: usize| -> Result<(), MyError> {
+        ensure_into!(a > 0 && a < limit);
+        Ok(())
+    };
+    let error = test(7, 5).unwrap_err().0;
+    assert_eq!(
+        error.to_string(),
+        "Condition failed: `a > 0 && a < limit`\n  a = 7\n  limit = 5",
+    );
+
+    let test = |ok: bool| -> Result<(), MyError> {
+        ensure_into!(ok, "not ok");
+        Ok(())
+    };
+    assert_eq!(test(false).unwrap_err().0.to_string(), "not ok");
+
+    let test = |a: usize| -> Result<(), MyError> {
+        ensure_into!(a == 0, "expected zero, got {}", a);
+        Ok(())
+    };
+    assert_eq!(test(1).unwrap_err().0.to_string(), "expected zero, got 1");
+
+    let test = |ok: bool| -> Result<(), MyError> {
+        ensure_into!(!ok);
+        Ok(())
+    };
+    assert_eq!(
+        test(true).unwrap_err().0.to_string(),
+        "Condition failed: `!ok`"
+    );
+}
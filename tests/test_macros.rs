@@ -13,7 +13,7 @@
 mod common;
 
 use self::common::*;
-use anyhow::{anyhow, ensure, Result};
+use anyhow::{anyhow, bail, ensure, Catalog, Context as _, Result, Severity};
 use std::cell::Cell;
 use std::future;
 
@@ -95,3 +95,46 @@ fn test_brace_escape() {
     let err = anyhow!("unterminated ${{..}} expression");
     assert_eq!("unterminated ${..} expression", err.to_string());
 }
+
+#[test]
+fn test_code() {
+    let err = anyhow!(code = "E0001", "oh no!");
+    assert_eq!(Some("E0001"), err.code());
+    assert_eq!("oh no!", err.to_string());
+
+    let f = |n: i32| -> Result<()> {
+        bail!(code = "E0002", "{} is not allowed", n);
+    };
+    let err = f(1).context("outer").unwrap_err();
+    assert_eq!(Some("E0002"), err.code());
+    assert_eq!("outer: 1 is not allowed", format!("{:#}", err));
+
+    let err = anyhow!("oh no!")
+        .with_code("E0003")
+        .context("outer")
+        .with_code("E0004");
+    assert_eq!(Some("E0004"), err.code());
+
+    static CATALOG: Catalog = Catalog::new(&[("E0001", "first"), ("E0002", "second")]);
+    assert_eq!(Some("second"), CATALOG.explain("E0002"));
+    assert!(!CATALOG.contains("E0003"));
+}
+
+#[test]
+fn test_severity() {
+    let err = anyhow!("oh no!");
+    assert_eq!(Severity::Error, err.severity());
+
+    let err = anyhow!(severity = Severity::Warning, code = "E0001", "oh {}!", "no");
+    assert_eq!(Severity::Warning, err.severity());
+    assert_eq!(Some("E0001"), err.code());
+
+    let f = || -> Result<()> {
+        bail!(severity = Severity::Fatal, "oh no!");
+    };
+    let err = f()
+        .context("outer")
+        .map_err(|err| err.with_severity(Severity::Debug))
+        .unwrap_err();
+    assert_eq!(Severity::Fatal, err.severity());
+}
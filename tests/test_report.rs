@@ -0,0 +1,56 @@
+// The report settings are process-wide, so everything that changes them is
+// exercised from a single test.
+
+use anyhow::report::{self, Color, Format};
+use anyhow::{anyhow, Context, Result};
+
+fn error() -> anyhow::Error {
+    let result: Result<()> = Err(anyhow!("oh \"no\"!"));
+    result
+        .context("middle")
+        .context("outer\nline")
+        .unwrap_err()
+        .with_code("E0001")
+        .help("try again")
+}
+
+#[test]
+fn test_report() {
+    report::set_format(Format::Oneline);
+    assert_eq!(Format::Oneline, report::format());
+    assert_eq!(
+        "[E0001] outer line: middle: oh \"no\"!",
+        format!("{:?}", error()),
+    );
+
+    report::set_format(Format::Json);
+    assert_eq!(
+        r#"{"message":"outer\nline","causes":["middle","oh \"no\"!"],"code":"E0001","severity":"error","sections":[{"kind":"help","message":"try again"}]}"#,
+        format!("{:?}", error()),
+    );
+
+    report::set_max_depth(Some(1));
+    assert_eq!(
+        r#"{"message":"outer\nline","causes":["middle"],"code":"E0001","severity":"error","sections":[{"kind":"help","message":"try again"}]}"#,
+        format!("{:?}", error()),
+    );
+
+    report::set_format(Format::Full);
+    report::set_color(Color::Always);
+    assert_eq!(
+        "\x1b[1;31m[E0001]\x1b[0m outer\nline\n\n\x1b[1mCaused by:\x1b[0m\n    0: middle\n    ... 1 more\n\n\x1b[1;36mhelp:\x1b[0m try again",
+        format!("{:?}", error()),
+    );
+
+    report::set_color(Color::Never);
+    report::set_max_depth(None);
+    assert_eq!(
+        "[E0001] outer\nline\n\nCaused by:\n    0: middle\n    1: oh \"no\"!\n\nhelp: try again",
+        format!("{:?}", error()),
+    );
+
+    // Alternate representations are unaffected.
+    report::set_format(Format::Json);
+    assert_eq!("outer\nline: middle: oh \"no\"!", format!("{:#}", error()));
+    report::set_format(Format::Full);
+}
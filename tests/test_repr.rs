@@ -4,6 +4,8 @@ mod drop;
 
 use self::drop::{DetectDrop, Flag};
 use anyhow::Error;
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
 use std::mem;
 
 #[test]
@@ -16,6 +18,28 @@ fn test_null_pointer_optimization() {
     assert_eq!(mem::size_of::<Result<(), Error>>(), mem::size_of::<usize>());
 }
 
+#[test]
+fn test_error_impl_size() {
+    #[derive(Debug)]
+    struct Zst;
+
+    impl Display for Zst {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("zst")
+        }
+    }
+
+    impl StdError for Zst {}
+
+    // Converting to Box<dyn Error> keeps the original allocation, whose size
+    // is that of the ErrorImpl holding the error.
+    let boxed: Box<dyn StdError + Send + Sync> = Error::new(Zst).into();
+
+    // Vtable pointer and attachments pointer. The backtrace, if captured, is
+    // stored out of line with the attachments.
+    assert_eq!(mem::size_of_val(&*boxed), 2 * mem::size_of::<usize>());
+}
+
 #[test]
 fn test_autotraits() {
     fn assert<E: Unpin + Send + Sync + 'static>() {}
@@ -1,4 +1,4 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, Error, Result};
 use std::error::Error as StdError;
 use std::fmt::{self, Display};
 use std::io;
@@ -60,3 +60,40 @@ fn test_anyhow_from_anyhow() {
     let error = anyhow!(error);
     assert_eq!("oh no!", error.source().unwrap().to_string());
 }
+
+#[test]
+fn test_msg_with_source() {
+    let io = io::Error::new(io::ErrorKind::NotFound, "no such file");
+    let error = Error::msg_with_source("failed to open", TestError::Io(io));
+    assert_eq!("failed to open", error.to_string());
+    assert_eq!("no such file", error.source().unwrap().to_string());
+    assert_eq!("no such file", error.root_cause().to_string());
+    assert_eq!(3, error.chain().count());
+    assert!(error.downcast_ref::<TestError>().is_some());
+    assert_eq!("failed to open", *error.downcast_ref::<&str>().unwrap());
+}
+
+#[test]
+fn test_macro_source() {
+    let io = io::Error::new(io::ErrorKind::NotFound, "no such file");
+    let path = "config.toml";
+    let error = anyhow!(source = io, "failed to open {}", path);
+    assert_eq!("failed to open config.toml", error.to_string());
+    assert_eq!("no such file", error.root_cause().to_string());
+    assert!(error.downcast_ref::<io::Error>().is_some());
+    assert!(error.downcast_ref::<String>().is_some());
+
+    let io = io::Error::new(io::ErrorKind::Other, "oh no!");
+    let error = anyhow!(code = "E0001", source = io, "context");
+    assert_eq!("context", error.to_string());
+    assert_eq!(Some("E0001"), error.code());
+    assert_eq!("oh no!", error.source().unwrap().to_string());
+
+    let test = || -> Result<()> {
+        let io = io::Error::new(io::ErrorKind::Other, "oh no!");
+        bail!(source = io, "failed");
+    };
+    let error = test().unwrap_err();
+    assert_eq!("failed", error.to_string());
+    assert!(error.downcast_ref::<io::Error>().is_some());
+}
@@ -0,0 +1,38 @@
+#![cfg(all(feature = "std", not(feature = "no-backtrace")))]
+
+use anyhow::backtrace::{self, Policy};
+use anyhow::{anyhow, Context as _};
+use std::fmt::Display;
+use std::io;
+
+fn capture() -> Option<Box<dyn Display + Send + Sync>> {
+    Some(Box::new("in task `main`\n  in task `worker`"))
+}
+
+#[test]
+fn test_capturer() {
+    backtrace::set_thread_policy(Some(Policy::Never));
+
+    let error = anyhow!("oh no!");
+    assert!(error.trace().is_none());
+
+    backtrace::set_capturer(capture);
+    let error = anyhow!("oh no!");
+    backtrace::clear_capturer();
+    let trace = error.trace().unwrap().to_string();
+    assert_eq!("in task `main`\n  in task `worker`", trace);
+
+    let expected = "oh no!\n\nTrace:\nin task `main`\n  in task `worker`";
+    assert_eq!(expected, format!("{:?}", error));
+
+    // Context layers keep the trace of the error they wrap.
+    backtrace::set_capturer(capture);
+    let error = Err::<(), _>(io::Error::other("oh no!"))
+        .context("context")
+        .unwrap_err()
+        .context("more context");
+    backtrace::clear_capturer();
+    assert_eq!(trace, error.trace().unwrap().to_string());
+
+    backtrace::set_thread_policy(None);
+}
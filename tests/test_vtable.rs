@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Context as _, Error};
+use std::collections::HashSet;
+use std::{fmt, io};
+
+#[test]
+fn test_vtables() {
+    let io = || io::Error::from(io::ErrorKind::Other);
+    let parse = || "x".parse::<i32>().unwrap_err();
+    let errors = [
+        anyhow!("oh no!"),
+        anyhow!("oh no! {}", 1),
+        Error::new(io()),
+        Error::new(fmt::Error),
+        Error::new(parse()),
+        Err::<(), _>(io()).context("oh no!").unwrap_err(),
+        Err::<(), _>(fmt::Error).context("oh no!").unwrap_err(),
+        Err::<(), _>(parse()).context("oh no!").unwrap_err(),
+        Err::<(), _>(io())
+            .with_context(|| format!("oh no! {}", 1))
+            .unwrap_err(),
+        Err::<(), _>(fmt::Error)
+            .with_context(|| format!("oh no! {}", 1))
+            .unwrap_err(),
+        Err::<(), _>(parse())
+            .with_context(|| format!("oh no! {}", 1))
+            .unwrap_err(),
+        anyhow!("oh no!").context("oh no!"),
+        anyhow!("oh no!").context(format!("oh no! {}", 1)),
+    ];
+
+    let vtables: HashSet<usize> = errors
+        .iter()
+        .flat_map(anyhow::__private::vtable_addrs)
+        .collect();
+    if cfg!(feature = "min-code-size") {
+        // One for each of the three error types, one shared by &'static str
+        // and String messages, and one shared by all string context, which
+        // wraps the error types above when they are not anyhow::Error.
+        assert_eq!(vtables.len(), 5);
+    } else {
+        // One per message type, one per error type, one per combination of
+        // context type and error type, and one per context type on
+        // anyhow::Error.
+        assert_eq!(vtables.len(), 13);
+    }
+
+    for error in &errors[5..11] {
+        assert!(error.to_string().starts_with("oh no!"));
+        assert!(
+            error.downcast_ref::<io::Error>().is_some()
+                || error.downcast_ref::<fmt::Error>().is_some()
+                || error.downcast_ref::<std::num::ParseIntError>().is_some()
+        );
+    }
+}
@@ -0,0 +1,7 @@
+use anyhow::{ensure, Result};
+
+fn main() -> Result<()> {
+    ensure!("...");
+    ensure!(1 + 1, "message");
+    Ok(())
+}
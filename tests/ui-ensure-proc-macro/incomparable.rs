@@ -0,0 +1,8 @@
+use anyhow::{ensure, Result};
+
+fn main() -> Result<()> {
+    let n = 1;
+    ensure!(n == "1");
+    ensure!(n.len() == 1);
+    Ok(())
+}
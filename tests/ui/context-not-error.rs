@@ -0,0 +1,12 @@
+use anyhow::Context;
+
+#[derive(Debug)]
+struct NotError;
+
+fn main() {
+    let result: Result<(), NotError> = Err(NotError);
+    let _ = Context::context(result, "oh no!");
+
+    let result: Result<(), NotError> = Err(NotError);
+    let _ = result.context("oh no!");
+}
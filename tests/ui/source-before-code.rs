@@ -0,0 +1,9 @@
+use anyhow::anyhow;
+use std::io;
+
+fn main() {
+    let err = io::Error::new(io::ErrorKind::Other, "oh no!");
+    let _ = anyhow!(source = err, code = "E0001", "context");
+    let err = io::Error::new(io::ErrorKind::Other, "oh no!");
+    let _ = anyhow!(source = err, severity = anyhow::Severity::Warning, "context");
+}
@@ -0,0 +1,9 @@
+use anyhow::{anyhow, Error};
+
+#[derive(Debug)]
+struct NotError;
+
+fn main() {
+    let _ = anyhow!(source = NotError, "oh no!");
+    let _ = Error::msg_with_source("oh no!", NotError);
+}